@@ -124,6 +124,17 @@ class Chat {
     this.engine.container.domElement.appendChild(this.gui.wrapper);
     this.engine.container.domElement.appendChild(this.gui.input);
 
+    this.gui.input.addEventListener(
+      'keydown',
+      (e) => {
+        if (this.engine.inputs.namespace !== 'chat' || e.key !== 'Tab') return;
+
+        e.preventDefault();
+        this.handleTab();
+      },
+      false,
+    );
+
     this.gui.input.addEventListener(
       'keyup',
       (e) => {
@@ -159,9 +170,9 @@ class Chat {
       player,
     } = this.engine;
 
+    // server lists its commands after the controls
     if (value === '/help') {
       this.add({ type: 'INFO', body: HELP_TEXT });
-      return;
     }
 
     if (value === '/spectator') {
@@ -216,6 +227,30 @@ class Chat {
     this.history.reset();
   };
 
+  handleTab = () => {
+    const value = this.inputValue;
+    if (!value.startsWith('/')) return;
+
+    this.engine.network.server.sendEvent({
+      type: 'COMPLETE',
+      text: value,
+    });
+  };
+
+  handleCompletions = (text: string, completions: string[]) => {
+    // input changed while waiting for the server
+    if (text !== this.inputValue || !completions.length) return;
+
+    if (completions.length === 1) {
+      const words = text.split(' ');
+      words[words.length - 1] = completions[0];
+      this.inputValue = `${words.join(' ')} `;
+      return;
+    }
+
+    this.add({ type: 'INFO', body: completions.join('  ') });
+  };
+
   handleUp = () => {
     const previous = this.history.previous();
     if (previous) this.inputValue = previous;
@@ -235,12 +235,13 @@ class Chunk {
           this.altMeshes.set(type, []);
         }
 
-        const { positions, indices, uvs, aos, lights } = meshData[type];
+        const { positions, indices, uvs, aos, lights, atlases } = meshData[type];
 
         const positionNumComponents = 3;
         const uvNumComponents = 2;
         const occlusionNumComponents = 1;
         const lightNumComponents = 1;
+        const atlasNumComponents = 1;
 
         let geometries = this.geometries.get(type);
         if (!geometries) {
@@ -255,6 +256,7 @@ class Chunk {
         geometry.setAttribute('uv', new Float32BufferAttribute(uvs, uvNumComponents));
         geometry.setAttribute('ao', new Int32BufferAttribute(aos, occlusionNumComponents));
         geometry.setAttribute('light', new Int32BufferAttribute(lights, lightNumComponents));
+        geometry.setAttribute('atlas', new Int32BufferAttribute(atlases, atlasNumComponents));
         geometry.setIndex(Array.from(indices));
 
         const min = new Vector3(
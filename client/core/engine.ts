@@ -297,9 +297,10 @@ class Engine extends EventEmitter {
       subChunks,
       name,
       packs,
+      texturePacks,
       blocks,
       ranges,
-      uvSideCount,
+      pages,
       uvTextureSize,
       entities: prototypes,
     } = worldData;
@@ -309,7 +310,8 @@ class Engine extends EventEmitter {
     registry.blocks = blocks;
     registry.ranges = ranges;
     registry.packs = packs;
-    registry.countPerSide = uvSideCount;
+    registry.texturePacks = texturePacks;
+    registry.pages = pages;
     registry.textureSize = uvTextureSize;
 
     world.name = name;
@@ -347,6 +349,7 @@ class Engine extends EventEmitter {
     this.entities.tick();
     this.shadows.tick();
     this.particles.tick();
+    this.registry.tick();
     this.peers.tick();
     this.world.tick();
     this.rendering.tick();
@@ -400,8 +403,8 @@ class Engine extends EventEmitter {
     if (sideEffect)
       this.rendering.engine.network.server.sendEvent({
         type: 'CONFIG',
-        json: {
-          tickSpeed: this.tickSpeed,
+        config: {
+          tickSpeed: { value: this.tickSpeed },
         },
       });
   };
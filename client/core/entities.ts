@@ -166,6 +166,14 @@ class Entities {
     return entity;
   };
 
+  removeEntity = (id: string) => {
+    const entity = this.entities.get(id);
+    if (!entity) return;
+    this.engine.rendering.scene.remove(entity.mesh);
+    this.updates = this.updates.filter(([updateId]) => updateId !== id);
+    return this.entities.delete(id);
+  };
+
   removePhysical = (name: string) => {
     const entity = this.physicals.get(name);
     if (!entity) return;
@@ -7,6 +7,10 @@ import { Engine } from './engine';
 
 const { Message, ChatMessage } = protocol;
 
+// must stay in sync with `PROTOCOL_VERSION` on the server
+const PROTOCOL_VERSION = 1;
+const CAPABILITIES = ['compression'];
+
 type CustomWebSocket = WebSocket & {
   sendEvent: (event) => void;
   serverURL: string;
@@ -48,6 +52,10 @@ class Network {
     socket.hash = '';
     socket.searchParams.set('world', this.engine.world.name);
 
+    // token obtained from `POST /login`, if any
+    const token = localStorage.getItem('minejs-token');
+    if (token) socket.searchParams.set('token', token);
+
     const server = new WebSocket(socket.toString()) as CustomWebSocket;
     server.binaryType = 'arraybuffer';
     server.sendEvent = (event) => {
@@ -57,6 +65,13 @@ class Network {
       }
     };
     server.onopen = () => {
+      server.send(
+        Network.encode({
+          type: 'HANDSHAKE',
+          handshake: { version: PROTOCOL_VERSION, capabilities: CAPABILITIES },
+        }),
+      );
+
       this.engine.emit('connected');
       this.engine.world.handleReconnection();
       this.connected = true;
@@ -88,28 +103,43 @@ class Network {
   };
 
   onInit = (event) => {
-    const { world, player } = this.engine;
+    const { world, player, registry } = this.engine;
     const {
-      json: { id, time, tickSpeed, spawn, passables },
+      init: { id, time, tickSpeed, spawn, rotation, passables, weather, skylight, texturePack },
     } = event;
 
     player.id = id;
     player.teleport(spawn);
 
+    // players new to the world come without a rotation, all zeros
+    if (rotation && rotation.length === 4 && rotation.some((value: number) => value !== 0)) {
+      player.object.quaternion.set(rotation[0], rotation[1], rotation[2], rotation[3]);
+    }
+
     world.setTime(time, false);
+    world.setWeather(weather, skylight);
     world.setBlockData({ passables });
 
+    if (texturePack && texturePack !== registry.texturePack) registry.setTexturePack(texturePack);
+
     this.engine.setTick(tickSpeed, false);
     this.engine.emit('init');
   };
 
+  onError = (event) => {
+    const { text } = event;
+    this.engine.chat.add({ type: 'ERROR', body: text });
+  };
+
   onConfig = (event) => {
     const {
-      json: { time, tickSpeed },
+      config: { time, tickSpeed, weather, skylight, texturePack },
     } = event;
 
-    if (Helper.isNumber(time)) this.engine.world.setTime(time, false);
-    if (Helper.isNumber(tickSpeed)) this.engine.setTick(tickSpeed, false);
+    if (time) this.engine.world.setTime(time.value, false);
+    if (weather && skylight) this.engine.world.setWeather(weather, skylight.value);
+    if (tickSpeed) this.engine.setTick(tickSpeed.value, false);
+    if (texturePack) this.engine.registry.setTexturePack(texturePack);
   };
 
   onUpdate = (event) => {
@@ -143,12 +173,35 @@ class Network {
 
   onInfo = (event) => {
     const { debug } = this.engine;
-    const { biome } = event.json;
+    const { biome } = event.info || {};
     if (debug && biome) {
       debug.biome = biome;
     }
   };
 
+  onTeleport = (event) => {
+    const { world, player, peers } = this.engine;
+    const { voxel, world: worldName } = event.teleport || {};
+
+    // moving to another world, INIT follows with the new position
+    if (worldName && worldName !== world.name) {
+      Array.from(peers.players.keys()).forEach((id) => peers.leave(id));
+
+      world.name = worldName;
+      world.reloadChunks();
+      return;
+    }
+
+    if (voxel && voxel.length === 3) {
+      player.teleport(voxel);
+    }
+  };
+
+  onComplete = (event) => {
+    const { text, completions } = event;
+    this.engine.chat.handleCompletions(text, completions || []);
+  };
+
   onJoin = (event) => {
     const { text: id } = event;
 
@@ -192,10 +245,37 @@ class Network {
     this.engine.chat.add(message);
   };
 
+  onDespawn = (event) => {
+    const { entities } = this.engine;
+    const { entities: entitiesData } = event;
+
+    for (const { id } of entitiesData) {
+      entities.removeEntity(id);
+    }
+  };
+
+  onRegistry = async (event) => {
+    const { registry, world } = this.engine;
+    const {
+      registry: { passables },
+    } = event;
+
+    const worldData = await this.fetchData('/world', { world: world.name });
+
+    registry.reload(worldData);
+    world.setBlockData({ passables });
+    world.reloadChunks();
+  };
+
   onEvent = (event) => {
     const { type } = event;
 
     switch (type) {
+      case 'ERROR': {
+        this.onError(event);
+        break;
+      }
+
       case 'INIT': {
         this.onInit(event);
         break;
@@ -226,6 +306,16 @@ class Network {
         break;
       }
 
+      case 'TELEPORT': {
+        this.onTeleport(event);
+        break;
+      }
+
+      case 'COMPLETE': {
+        this.onComplete(event);
+        break;
+      }
+
       case 'LEAVE': {
         this.onLeave(event);
         break;
@@ -236,6 +326,21 @@ class Network {
         break;
       }
 
+      case 'KICK': {
+        this.onError(event);
+        break;
+      }
+
+      case 'DESPAWN': {
+        this.onDespawn(event);
+        break;
+      }
+
+      case 'REGISTRY': {
+        this.onRegistry(event);
+        break;
+      }
+
       case 'ENTITY': {
         this.onEntity(event);
         break;
@@ -279,9 +384,6 @@ class Network {
     const message = Message.decode(buffer);
     // @ts-ignore
     message.type = Message.Type[message.type];
-    if (message.json) {
-      message.json = JSON.parse(message.json);
-    }
     if (message.message) {
       // @ts-ignore
       message.message.type = ChatMessage.Type[message.message.type];
@@ -290,9 +392,6 @@ class Network {
   }
 
   static encode(message) {
-    if (message.json) {
-      message.json = JSON.stringify(message.json);
-    }
     message.type = Message.Type[message.type];
     if (message.message) {
       message.message.type = ChatMessage.Type[message.message.type];
@@ -1,5 +1,5 @@
 import TWEEN from '@tweenjs/tween.js';
-import { BufferGeometry, Float32BufferAttribute, Points, ShaderMaterial, Vector3, Object3D, Vector2 } from 'three';
+import { BufferGeometry, Float32BufferAttribute, Points, ShaderMaterial, Vector3, Object3D } from 'three';
 
 import { PhysicalType, Coords3 } from '../libs/types';
 import { Helper } from '../utils';
@@ -59,6 +59,10 @@ class Particles {
     const particles = [];
     const lights = [];
     const uvs = [];
+    const repeats = [];
+
+    // a group draws from a single atlas page, the one of the first broken block
+    let page: number;
 
     const geometry = new BufferGeometry();
 
@@ -72,12 +76,12 @@ class Particles {
       const blue = this.engine.world.getBlueLight(voxel) / 15;
       const sun = this.engine.world.getSunlight(voxel) / 15;
 
-      const typeUVObj = this.engine.registry.getUV(type);
-      const typeUVArr = [];
+      const ranges = this.engine.registry.getRanges(type);
+      if (ranges.length === 0) return;
+      if (page === undefined) page = ranges[0].page;
 
-      Object.values(typeUVObj).forEach((uv) => {
-        typeUVArr.push(uv[0][uv[1]]);
-      });
+      const typeRanges = ranges.filter((range) => range.page === page);
+      if (typeRanges.length === 0) return;
 
       for (let i = 0; i < count; i++) {
         const entity = this.engine.entities.addPhysical(
@@ -90,7 +94,9 @@ class Particles {
         );
         entity.body.applyImpulse(impulseFunc(force));
         particles.push(entity);
-        uvs.push(typeUVArr[i % typeUVArr.length]);
+        const { startU, endU, startV, endV } = typeRanges[i % typeRanges.length];
+        uvs.push([startU, endV]);
+        repeats.push([endU - startU, startV - endV]);
 
         // TODO: fix this. this is costly
         lights.push(red, green, blue, sun);
@@ -102,19 +108,16 @@ class Particles {
     }
 
     geometry.setAttribute('uv', new Float32BufferAttribute(Helper.flatten(uvs), 2));
+    geometry.setAttribute('repeat', new Float32BufferAttribute(Helper.flatten(repeats), 2));
     geometry.setAttribute('lights', new Float32BufferAttribute(lights, 4));
     geometry.attributes.uv.needsUpdate = true;
 
-    const { countPerSide, textureSize } = this.engine.config.registry;
     const material = new ShaderMaterial({
       uniforms: {
-        uTexture: { value: this.engine.registry.atlasUniform.value },
+        uTexture: { value: this.engine.registry.atlasPagesUniform.value[page] },
         uPointSize: {
           value: (window.innerHeight / (2.0 * Math.tan((0.5 * 60.0 * Math.PI) / 180.0))) * PARTICLE_SCALE,
         },
-        uRepeat: {
-          value: new Vector2(1 / countPerSide - (0.1 / textureSize) * 2, 1 / countPerSide - (0.1 / textureSize) * 2),
-        },
         uScale: {
           value: 1,
         },
@@ -10,12 +10,14 @@ import {
   OrthographicCamera,
   PlaneBufferGeometry,
   Scene,
+  ShaderChunk,
   ShaderLib,
   ShaderMaterial,
   sRGBEncoding,
   Texture,
   TextureLoader,
   UniformsUtils,
+  Vector3,
   Vector4,
   WebGLRenderer,
 } from 'three';
@@ -41,13 +43,26 @@ type Block = {
   transparentStandalone: boolean;
 };
 
+type UV = {
+  startU: number;
+  endU: number;
+  startV: number;
+  endV: number;
+  page: number;
+  frames: number;
+  frameTime: number;
+  frameOffset: number;
+  animation: number;
+};
+
 type Range = {
-  [key: string]: {
-    startU: number;
-    endU: number;
-    startV: number;
-    endV: number;
-  };
+  [key: string]: UV;
+};
+
+type PackType = {
+  ranges: Range;
+  pages: number;
+  uvTextureSize: number;
 };
 
 type RegistryOptionsType = {
@@ -55,19 +70,27 @@ type RegistryOptionsType = {
   focusBlockSize: number;
   focusPlantSize: number;
   resolution: number;
-  countPerSide?: number;
+  pages?: number;
   textureSize?: number;
   packs?: string[];
+  texturePacks?: { [name: string]: PackType };
   blocks?: Block[];
-  ranges?: Range[];
+  ranges?: Range;
 };
 
 const TRANSPARENT_SIDES = [FrontSide, BackSide];
 
+// keep in sync with the server's atlas limits
+const MAX_ATLAS_PAGES = 4;
+const MAX_ANIMATIONS = 64;
+
 class Registry {
   public texturePack: string;
 
   public atlasUniform: { value: Texture | null };
+  public atlasPagesUniform: { value: Texture[] };
+  public animationsUniform: { value: Vector3[] };
+  public timeUniform = { value: 0 };
   public aoUniform: { value: Vector4 };
 
   public opaqueChunkMaterial: ShaderMaterial;
@@ -78,7 +101,7 @@ class Registry {
   private canvas: HTMLCanvasElement;
   private camera: OrthographicCamera;
   private bufferScene: Scene;
-  private material: MeshBasicMaterial;
+  private materials: MeshBasicMaterial[] = [];
   private renderer: WebGLRenderer;
   private blockGeometry: BufferGeometry;
   private plantGeometry: BufferGeometry;
@@ -86,7 +109,8 @@ class Registry {
   constructor(public engine: Engine, public options: RegistryOptionsType) {
     const { focusDist, focusBlockSize, focusPlantSize, resolution } = options;
 
-    this.texturePack = this.options.packs.length > 0 ? this.options.packs[1] : this.options.packs[0];
+    // the server lists its default pack first, players may have picked another one
+    this.texturePack = this.options.packs[0];
 
     this.aoUniform = { value: new Vector4(100.0, 170.0, 210.0, 255.0) };
 
@@ -166,6 +190,12 @@ class Registry {
       this.atlasUniform = {
         value: null,
       };
+      this.atlasPagesUniform = {
+        value: [],
+      };
+      this.animationsUniform = {
+        value: new Array(MAX_ANIMATIONS).fill(0).map(() => new Vector3()),
+      };
 
       this.setTexturePack(this.texturePack, () => {
         engine.emit('texture-loaded');
@@ -191,29 +221,83 @@ class Registry {
     });
   }
 
-  setTexturePack = (packName: string, onFinish?: () => void) => {
-    this.atlasUniform.value = new TextureLoader().load(
-      `${this.engine.network.cleanURL}atlas/${packName}-atlas.png`,
-      () => {
-        if (onFinish) onFinish();
+  setTexturePack = (packName: string, onFinish?: () => void, bustCache = false) => {
+    this.texturePack = packName;
+
+    const pack = this.options.texturePacks?.[packName];
+    if (pack) {
+      this.options.ranges = pack.ranges;
+      this.options.pages = pack.pages;
+      this.options.textureSize = pack.uvTextureSize;
+    }
 
-        Object.keys(this.options.blocks).forEach((idStr) => {
-          const id = +idStr;
-          this.focuses[idStr] = this.focus(id);
-        });
+    // atlas gets loaded once the engine is ready
+    if (!this.atlasUniform) return;
 
-        this.engine.inventory.updateDOM();
-      },
-    );
+    this.updateAnimations();
 
-    const atlas = this.atlasUniform.value;
+    // the atlas keeps its URL when the server rebuilds it
+    const query = bustCache ? `?v=${Date.now()}` : '';
 
-    atlas.minFilter = NearestFilter;
-    atlas.magFilter = NearestFilter;
-    atlas.generateMipmaps = false;
-    atlas.encoding = sRGBEncoding;
+    const pageCount = Math.min(this.options.pages || 1, MAX_ATLAS_PAGES);
+    let loaded = 0;
 
-    this.material = new MeshBasicMaterial({ map: this.atlasUniform.value, side: DoubleSide });
+    const pages = new Array(pageCount).fill(0).map((_, page) => {
+      const atlas = new TextureLoader().load(
+        `${this.engine.network.cleanURL}atlas/${packName}-atlas-${page}.png${query}`,
+        () => {
+          loaded++;
+          if (loaded < pageCount) return;
+
+          if (onFinish) onFinish();
+
+          Object.keys(this.options.blocks).forEach((idStr) => {
+            const id = +idStr;
+            this.focuses[idStr] = this.focus(id);
+          });
+
+          this.engine.inventory.updateDOM();
+        },
+      );
+
+      atlas.minFilter = NearestFilter;
+      atlas.magFilter = NearestFilter;
+      atlas.generateMipmaps = false;
+      atlas.encoding = sRGBEncoding;
+
+      return atlas;
+    });
+
+    this.atlasPagesUniform.value = pages;
+    this.atlasUniform.value = pages[0];
+
+    this.materials = pages.map((atlas) => new MeshBasicMaterial({ map: atlas, side: DoubleSide }));
+  };
+
+  tick = () => {
+    this.timeUniform.value += this.engine.clock.delta;
+  };
+
+  updateAnimations = () => {
+    const animations = this.animationsUniform.value;
+    animations.forEach((animation) => animation.set(1, 1, 0));
+
+    Object.values(this.options.ranges).forEach(({ animation, frames, frameTime, frameOffset }) => {
+      if (!animation || animation >= MAX_ANIMATIONS) return;
+      animations[animation].set(frames, frameTime, frameOffset);
+    });
+  };
+
+  reload = ({ blocks, ranges, packs, texturePacks, pages, uvTextureSize }) => {
+    this.options.blocks = blocks;
+    this.options.ranges = ranges;
+    this.options.packs = packs;
+    this.options.texturePacks = texturePacks;
+    this.options.pages = pages;
+    this.options.textureSize = uvTextureSize;
+
+    this.focuses = {};
+    this.setTexturePack(this.texturePack, undefined, true);
   };
 
   focus = (id: number) => {
@@ -235,7 +319,7 @@ class Registry {
     const uvs = Helper.flatten([...nx[0], ...px[0], ...ny[0], ...py[0], ...nz[0], ...pz[0]]);
     this.blockGeometry.setAttribute('uv', new Float32BufferAttribute(uvs, 2));
 
-    const mesh = new Mesh(this.blockGeometry, this.material);
+    const mesh = new Mesh(this.blockGeometry, this.getMaterial(id));
     mesh.frustumCulled = false;
 
     while (this.bufferScene.children.length > 0) {
@@ -259,7 +343,7 @@ class Registry {
     const uvs = Helper.flatten(one[0]);
     this.plantGeometry.setAttribute('uv', new Float32BufferAttribute(uvs, 2));
 
-    const mesh = new Mesh(this.plantGeometry, this.material);
+    const mesh = new Mesh(this.plantGeometry, this.getMaterial(id));
     mesh.frustumCulled = false;
 
     while (this.bufferScene.children.length > 0) {
@@ -367,6 +451,13 @@ class Registry {
     return {};
   };
 
+  getRanges = (id: number): UV[] => {
+    const { textures } = this.options.blocks[id];
+    return Object.values(textures)
+      .map((file) => this.options.ranges[file])
+      .filter((range) => !!range);
+  };
+
   getFocus = (id: number) => {
     return this.focuses[id.toString()];
   };
@@ -387,6 +478,12 @@ class Registry {
     return !!this.options.blocks[id];
   };
 
+  private getMaterial = (id: number) => {
+    // icons are drawn from a single page, the one of the block's first texture
+    const [range] = this.getRanges(id);
+    return this.materials[range ? range.page : 0] || this.materials[0];
+  };
+
   private makeShaderMaterial = () => {
     const material = new ShaderMaterial({
       vertexColors: true,
@@ -400,19 +497,40 @@ uniform vec3 uFogNearColor;
 uniform float uFogNear;
 uniform float uFogFar;
 uniform float uSunlightIntensity;
+uniform sampler2D uAtlases[${MAX_ATLAS_PAGES}];
 
 varying float vAO;
 varying vec4 vLight; 
+varying float vPage;
+varying float vMixed;
+varying vec3 vDayLight;
+varying vec3 vNightLight;
+
+vec4 sampleAtlas(vec2 uv) {
+  if (vPage < 0.5) return texture2D(uAtlases[0], uv);
+  if (vPage < 1.5) return texture2D(uAtlases[1], uv);
+  if (vPage < 2.5) return texture2D(uAtlases[2], uv);
+  return texture2D(uAtlases[3], uv);
+}
 `,
         )
+        .replace(
+          '#include <map_fragment>',
+          ShaderChunk.map_fragment.replace('texture2D( map, vUv )', 'sampleAtlas( vUv )'),
+        )
         .replace(
           '#include <envmap_fragment>',
           `
 #include <envmap_fragment>
-float s = max(vLight.a * uSunlightIntensity * 0.8, 0.02);
-float scale = 1.0;
-outgoingLight.rgb *= vec3(s + pow(vLight.r, scale), s + pow(vLight.g, scale), s + pow(vLight.b, scale));
-// outgoingLight.rgb *= vec3(s + scale / sqrt(vLight.r), s + scale / sqrt(vLight.g), s + scale / sqrt(vLight.b));
+if (vMixed > 0.5) {
+  // lights mixed by the server, from night (0.1 of sunlight) to day
+  outgoingLight.rgb *= mix(vNightLight, vDayLight, clamp((uSunlightIntensity - 0.1) / 0.9, 0.0, 1.0));
+} else {
+  float s = max(vLight.a * uSunlightIntensity * 0.8, 0.02);
+  float scale = 1.0;
+  outgoingLight.rgb *= vec3(s + pow(vLight.r, scale), s + pow(vLight.g, scale), s + pow(vLight.b, scale));
+  // outgoingLight.rgb *= vec3(s + scale / sqrt(vLight.r), s + scale / sqrt(vLight.g), s + scale / sqrt(vLight.b));
+}
 outgoingLight *= 0.88 * vAO;
 `,
         )
@@ -430,11 +548,19 @@ gl_FragColor.rgb = mix(gl_FragColor.rgb, mix(uFogNearColor, uFogColor, fogFactor
           `
 attribute int ao;
 attribute int light;
+attribute int atlas;
 
 varying float vAO;
 varying vec4 vLight;
+varying float vPage;
+varying float vMixed;
+varying vec3 vDayLight;
+varying vec3 vNightLight;
 
 uniform vec4 uAOTable;
+uniform float uTime;
+// frame count, frame time and frame offset of each animated texture
+uniform vec3 uAnimations[${MAX_ANIMATIONS}];
 
 vec4 unpackLight(int l) {
   float r = float((l >> 8) & 0xF) / 15.0;
@@ -444,6 +570,10 @@ vec4 unpackLight(int l) {
   return vec4(r, g, b, s);
 }
 
+vec3 unpackMixedLight(int l) {
+  return vec3(float((l >> 8) & 0xF), float((l >> 4) & 0xF), float(l & 0xF)) / 15.0;
+}
+
 #include <common>
 `,
         )
@@ -456,12 +586,32 @@ vAO = ((ao == 0) ? uAOTable.x :
     (ao == 1) ? uAOTable.y :
     (ao == 2) ? uAOTable.z : uAOTable.w) / 255.0; 
 vLight = unpackLight(light);
+vMixed = float((light >> 24) & 1);
+vDayLight = unpackMixedLight(light >> 12);
+vNightLight = unpackMixedLight(light);
+`,
+        )
+        .replace(
+          '#include <uv_vertex>',
+          `
+#include <uv_vertex>
+
+vPage = float(atlas & 0xFF);
+
+int animation = atlas >> 8;
+if (animation > 0) {
+  vec3 frames = uAnimations[animation];
+  vUv.x += mod(floor(uTime / frames.y), frames.x) * frames.z;
+}
 `,
         ),
 
       uniforms: {
         ...UniformsUtils.clone(ShaderLib.basic.uniforms),
         map: this.atlasUniform,
+        uAtlases: this.atlasPagesUniform,
+        uAnimations: this.animationsUniform,
+        uTime: this.timeUniform,
         uSunlightIntensity: this.engine.world.uSunlightIntensity,
         uAOTable: this.aoUniform,
         ...this.engine.rendering.fogUniforms,
@@ -475,4 +625,4 @@ vLight = unpackLight(light);
   };
 }
 
-export { Registry, RegistryOptionsType };
+export { Registry, RegistryOptionsType, UV };
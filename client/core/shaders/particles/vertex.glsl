@@ -2,8 +2,10 @@ uniform float uPointSize;
 uniform float uScale;
 
 attribute vec4 lights;
+attribute vec2 repeat;
 
 varying vec2 vUv;
+varying vec2 vRepeat;
 varying vec4 vLights;
 
 void main() {
@@ -12,5 +14,6 @@ void main() {
   gl_PointSize = 0.2 * uPointSize * uScale / gl_Position.w;
   
   vUv = uv;
+  vRepeat = repeat;
   vLights = lights;
 }
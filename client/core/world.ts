@@ -36,6 +36,10 @@ class World extends EventEmitter {
   // uniforms
   public uSunlightIntensity = { value: 0.1 };
 
+  // server-side weather, and how much of the sunlight it lets through
+  public weather = 'clear';
+  public skylight = 1;
+
   public blockData: { passables: number[] } = {
     passables: [],
   };
@@ -322,13 +326,18 @@ class World extends EventEmitter {
     if (sideEffect) {
       this.engine.network.server.sendEvent({
         type: 'CONFIG',
-        json: {
-          time: this.sky.tracker.time,
+        config: {
+          time: { value: this.sky.tracker.time },
         },
       });
     }
   };
 
+  setWeather = (weather: string, skylight: number) => {
+    if (weather) this.weather = weather;
+    if (skylight) this.skylight = skylight;
+  };
+
   setBlockData = ({ passables }) => {
     if (passables && passables.length) this.blockData.passables = passables;
   };
@@ -474,7 +483,7 @@ class World extends EventEmitter {
       if (this.requestedChunks.has(rep)) return;
       this.engine.network.server.sendEvent({
         type: 'REQUEST',
-        json: { x: cx, z: cz },
+        request: { x: cx, z: cz },
       });
       this.requestedChunks.add(rep);
     });
@@ -459,8 +459,12 @@ class Sky {
 
     // lerp sunlight
     const sunlightLerpFactor = 0.008 * speed * delta;
-    const { uSunlightIntensity } = this.rendering.engine.world;
-    uSunlightIntensity.value = MathUtils.lerp(uSunlightIntensity.value, tracker.sunlight, sunlightLerpFactor);
+    const { uSunlightIntensity, skylight } = this.rendering.engine.world;
+    uSunlightIntensity.value = MathUtils.lerp(
+      uSunlightIntensity.value,
+      Math.max(0.1, tracker.sunlight * skylight),
+      sunlightLerpFactor,
+    );
 
     const cloudColor = this.rendering.engine.world.clouds.material.uniforms.uCloudColor.value;
     const cloudColorHSL = cloudColor.getHSL({});
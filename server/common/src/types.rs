@@ -13,6 +13,25 @@ pub struct UV {
     pub end_u: f32,
     pub start_v: f32,
     pub end_v: f32,
+    /// Atlas page the texture is drawn on
+    pub page: u32,
+    /// Frames of the texture, laid out left to right, 1 if it's not animated
+    pub frames: u32,
+    /// Seconds each frame is shown for
+    pub frame_time: f32,
+    /// Distance in U from one frame to the next
+    pub frame_offset: f32,
+    /// Index of the texture among the animated ones, starting at 1, 0 if it's not animated
+    pub animation: u32,
+}
+
+/// Animation of a texture drawn as a strip of frames stacked top to bottom
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Animation {
+    pub frames: u32,
+    /// Seconds each frame is shown for
+    pub frame_time: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,8 +53,38 @@ pub struct Block {
     pub is_plantable: bool,
     pub transparent_standalone: bool,
 
+    /// Light levels lost on top of the usual one when light enters this block, so leaves
+    /// cast shadows and light fades faster in water
+    #[serde(default)]
+    pub light_reduction: u32,
+
+    /// Share of red, green and blue light let through, from 0 to 1, if this block tints
+    /// or dims the light passing through it. Sunlight is dimmed by the average.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub light_filter: Option<[f32; 3]>,
+
     #[serde(default)]
     pub textures: HashMap<String, String>,
+
+    /// Animations of the textures of this block that are strips of frames
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub animations: HashMap<String, Animation>,
+
+    /// Where walking into this block takes players, if it's a portal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub portal: Option<Portal>,
+}
+
+/// Destination of a portal block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Portal {
+    /// Name of the world to go to, empty for the world the portal is in
+    #[serde(default)]
+    pub world: String,
+    /// Voxel to stand on, instead of the other world's spawn
+    #[serde(default)]
+    pub position: Option<[i32; 3]>,
 }
 
 #[derive(Debug, Clone)]
@@ -45,6 +94,8 @@ pub struct MeshType {
     pub uvs: Vec<f32>,
     pub aos: Vec<i32>,
     pub lights: Vec<i32>,
+    /// Atlas page of each vertex's texture, with its animation index shifted 8 bits up
+    pub atlases: Vec<i32>,
 }
 
 pub type GetVoxel<'a> = &'a dyn Fn(i32, i32, i32) -> bool;
@@ -0,0 +1,78 @@
+use std::time::Instant;
+
+use server_common::vec::Vec3;
+use specs::{Component, VecStorage};
+
+/// Quantized snapshot of an entity's networked state
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EntitySnapshot {
+    pub position: Vec3<f32>,
+    pub heading: Option<Vec3<f32>>,
+    pub look_at: Option<Vec3<f32>>,
+}
+
+impl EntitySnapshot {
+    /// Create a snapshot, snapping the position to `position_step` and
+    /// the directions to `direction_step`.
+    pub fn quantized(
+        position: &Vec3<f32>,
+        heading: &Option<Vec3<f32>>,
+        look_at: &Option<Vec3<f32>>,
+        position_step: f32,
+        direction_step: f32,
+    ) -> Self {
+        Self {
+            position: quantize(position, position_step),
+            heading: heading.as_ref().map(|h| quantize(h, direction_step)),
+            look_at: look_at.as_ref().map(|l| quantize(l, direction_step)),
+        }
+    }
+}
+
+/// Keeps track of what has been sent to the clients about an entity,
+/// so that unchanged entities aren't sent every tick.
+#[derive(Default, Component)]
+#[storage(VecStorage)]
+pub struct EntitySync {
+    pub last_sent: Option<EntitySnapshot>,
+    pub last_keyframe: Option<Instant>,
+}
+
+impl EntitySync {
+    pub fn new() -> Self {
+        Self {
+            last_sent: None,
+            last_keyframe: None,
+        }
+    }
+
+    /// Returns true if the snapshot differs from the last sent one, or if
+    /// a keyframe is due after `keyframe_interval` milliseconds.
+    pub fn should_send(&self, snapshot: &EntitySnapshot, keyframe_interval: u64) -> bool {
+        let keyframe_due = match self.last_keyframe {
+            Some(instant) => instant.elapsed().as_millis() as u64 >= keyframe_interval,
+            None => true,
+        };
+
+        keyframe_due || self.last_sent.as_ref() != Some(snapshot)
+    }
+
+    /// Record a snapshot as sent. Resets the keyframe timer.
+    pub fn mark_sent(&mut self, snapshot: EntitySnapshot) {
+        self.last_sent = Some(snapshot);
+        self.last_keyframe = Some(Instant::now());
+    }
+}
+
+/// Snap each component of a vector onto a grid of size `step`
+fn quantize(vec: &Vec3<f32>, step: f32) -> Vec3<f32> {
+    if step <= 0.0 {
+        return vec.clone();
+    }
+
+    Vec3(
+        (vec.0 / step).round() * step,
+        (vec.1 / step).round() * step,
+        (vec.2 / step).round() * step,
+    )
+}
@@ -1,5 +1,6 @@
 pub mod brain;
 pub mod curr_chunk;
+pub mod entity_sync;
 pub mod etype;
 pub mod id;
 pub mod name;
@@ -298,3 +298,22 @@ pub const CHUNK_HORIZONTAL_NEIGHBORS: [[i32; 2]; 4] = [[-1, 0], [1, 0], [0, -1],
 pub const DATA_PADDING: usize = 1;
 
 pub const WORLD_DATA_FILE: &str = "world.json";
+
+pub const PALETTE_FILE: &str = "palette.json";
+
+pub const MIGRATION_FOLDER: &str = "chunks.migrating";
+
+pub const PLAYERS_FOLDER: &str = "players";
+
+pub const ROLES_FILE: &str = "roles.json";
+
+pub const WORLDS_FILE: &str = "assets/metadata/worlds.json";
+
+pub const BANS_FILE: &str = "data/bans.json";
+
+/// Worlds created at runtime, kept apart from the shipped `WORLDS_FILE`
+pub const CREATED_WORLDS_FILE: &str = "data/worlds.json";
+
+pub const MUTES_FILE: &str = "data/mutes.json";
+
+pub const SPAWN_SEARCH_RADIUS: i32 = 16;
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+
+use image::{imageops, RgbaImage};
+
+use server_common::types::{Animation, UV};
+
+/// Units a tile side is split into. Textures are laid out in units rather than texels,
+/// so every pack gets the same layout whatever its dimension.
+pub const TILE_UNITS: u32 = 16;
+
+/// Border around each frame, in units, filled with its extruded edges so mipmaps don't bleed
+pub const PADDING_UNITS: u32 = 1;
+
+/// Largest side of an atlas page in texels, for the biggest pack dimension
+pub const MAX_ATLAS_SIZE: u32 = 4096;
+
+/// Pages clients can sample from
+pub const MAX_ATLAS_PAGES: u32 = 4;
+
+/// Size of the animation table clients keep, index 0 meaning still
+pub const MAX_ANIMATIONS: u32 = 64;
+
+/// Size of a texture in tiles, and how many frames it's made of
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shape {
+    pub width: u32,
+    pub height: u32,
+    pub frames: u32,
+}
+
+impl Shape {
+    /// Shape of a texture image of a pack with tiles `dimension` texels wide
+    pub fn of(image: &RgbaImage, dimension: u32, frames: u32) -> Self {
+        let tiles = |texels: u32| ((texels as f32 / dimension as f32).round() as u32).max(1);
+
+        Self {
+            width: tiles(image.width()),
+            height: tiles(image.height() / frames.max(1)),
+            frames: frames.max(1),
+        }
+    }
+
+    /// Width and height of a single frame with its padding, in units
+    fn cell(&self) -> (u32, u32) {
+        (
+            self.width * TILE_UNITS + PADDING_UNITS * 2,
+            self.height * TILE_UNITS + PADDING_UNITS * 2,
+        )
+    }
+}
+
+/// Where a texture starts on the atlas, in units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub page: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Positions of every texture on the atlas pages, shared by every pack
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub textures: HashMap<String, (Shape, Placement)>,
+    /// Width and height of each page in units, powers of two
+    pub pages: Vec<(u32, u32)>,
+}
+
+impl Layout {
+    /// Shelf-pack textures onto pages at most `max_units` wide and high, tallest first
+    pub fn pack(shapes: &HashMap<String, Shape>, max_units: u32) -> Result<Self, String> {
+        let mut sources = shapes.iter().collect::<Vec<_>>();
+        sources.sort_by(|(a_name, a), (b_name, b)| {
+            let (a_width, a_height) = a.cell();
+            let (b_width, b_height) = b.cell();
+
+            (b_height, b_width * b.frames, a_name).cmp(&(a_height, a_width * a.frames, b_name))
+        });
+
+        let mut textures = HashMap::new();
+        let mut pages: Vec<(u32, u32)> = vec![];
+
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+        for (source, shape) in sources {
+            let (cell_width, height) = shape.cell();
+            let width = cell_width * shape.frames;
+
+            if width > max_units || height > max_units {
+                return Err(format!("Texture {} doesn't fit on an atlas page.", source));
+            }
+
+            if pages.is_empty() {
+                pages.push((0, 0));
+            }
+
+            if x + width > max_units {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+
+            if y + height > max_units {
+                pages.push((0, 0));
+                x = 0;
+                y = 0;
+                shelf_height = 0;
+            }
+
+            let page = pages.len() - 1;
+            let (used_width, used_height) = &mut pages[page];
+            *used_width = (*used_width).max(x + width);
+            *used_height = (*used_height).max(y + height);
+
+            let placement = Placement {
+                page: page as u32,
+                x,
+                y,
+            };
+            textures.insert(source.to_owned(), (*shape, placement));
+
+            x += width;
+            shelf_height = shelf_height.max(height);
+        }
+
+        let animated = shapes.values().filter(|shape| shape.frames > 1).count() as u32;
+
+        if animated >= MAX_ANIMATIONS {
+            return Err(format!(
+                "{} textures are animated, at most {} are supported.",
+                animated,
+                MAX_ANIMATIONS - 1
+            ));
+        }
+
+        if pages.len() as u32 > MAX_ATLAS_PAGES {
+            return Err(format!(
+                "Textures need {} atlas pages, at most {} are supported.",
+                pages.len(),
+                MAX_ATLAS_PAGES
+            ));
+        }
+
+        let pages = pages
+            .into_iter()
+            .map(|(width, height)| (width.next_power_of_two(), height.next_power_of_two()))
+            .collect();
+
+        Ok(Self { textures, pages })
+    }
+
+    /// UV ranges of the first frame of every texture. Animated textures are numbered
+    /// in name order, so the same animations always get the same index.
+    pub fn ranges(&self, animations: &HashMap<String, Animation>) -> HashMap<String, UV> {
+        let mut animated = animations
+            .iter()
+            .filter(|(source, animation)| {
+                animation.frames > 1 && self.textures.contains_key(*source)
+            })
+            .map(|(source, _)| source)
+            .collect::<Vec<_>>();
+        animated.sort();
+
+        self.textures
+            .iter()
+            .map(|(source, (shape, placement))| {
+                let (page_width, page_height) = self.pages[placement.page as usize];
+                let (page_width, page_height) = (page_width as f32, page_height as f32);
+
+                let start_x = (placement.x + PADDING_UNITS) as f32;
+                let start_y = (placement.y + PADDING_UNITS) as f32;
+                let end_x = start_x + (shape.width * TILE_UNITS) as f32;
+                let end_y = start_y + (shape.height * TILE_UNITS) as f32;
+
+                let animation = animated
+                    .iter()
+                    .position(|&animated| animated == source)
+                    .map_or(0, |index| index as u32 + 1);
+
+                let uv = UV {
+                    start_u: start_x / page_width,
+                    end_u: end_x / page_width,
+                    start_v: 1.0 - start_y / page_height,
+                    end_v: 1.0 - end_y / page_height,
+                    page: placement.page,
+                    frames: shape.frames,
+                    frame_time: animations.get(source).map_or(0.0, |a| a.frame_time),
+                    frame_offset: shape.cell().0 as f32 / page_width,
+                    animation,
+                };
+
+                (source.to_owned(), uv)
+            })
+            .collect()
+    }
+
+    /// Draw the textures of a pack with tiles `dimension` texels wide onto the pages
+    pub fn draw(&self, images: &HashMap<String, RgbaImage>, dimension: u32) -> Vec<RgbaImage> {
+        let unit = dimension / TILE_UNITS;
+        let padding = PADDING_UNITS * unit;
+
+        let mut pages = self
+            .pages
+            .iter()
+            .map(|&(width, height)| RgbaImage::new(width * unit, height * unit))
+            .collect::<Vec<_>>();
+
+        for (source, (shape, placement)) in &self.textures {
+            let image = match images.get(source) {
+                Some(image) => image,
+                None => continue,
+            };
+
+            let frame_width = shape.width * dimension;
+            let frame_height = shape.height * dimension;
+
+            let strip = imageops::resize(
+                image,
+                frame_width,
+                frame_height * shape.frames,
+                imageops::FilterType::CatmullRom,
+            );
+
+            let page = &mut pages[placement.page as usize];
+            let (cell_width, _) = shape.cell();
+
+            for frame in 0..shape.frames {
+                let start_x = (placement.x + frame * cell_width) * unit;
+                let start_y = placement.y * unit;
+                let offset_y = frame * frame_height;
+
+                // copy the frame with its edges stretched over the padding around it
+                for dy in 0..frame_height + padding * 2 {
+                    for dx in 0..frame_width + padding * 2 {
+                        let sx = dx.saturating_sub(padding).min(frame_width - 1);
+                        let sy = dy.saturating_sub(padding).min(frame_height - 1);
+
+                        let pixel = *strip.get_pixel(sx, offset_y + sy);
+                        page.put_pixel(start_x + dx, start_y + dy, pixel);
+                    }
+                }
+            }
+        }
+
+        pages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(width: u32, height: u32, frames: u32) -> Shape {
+        Shape {
+            width,
+            height,
+            frames,
+        }
+    }
+
+    #[test]
+    fn packs_without_overlaps() {
+        let mut shapes = HashMap::new();
+        shapes.insert("a.png".to_owned(), shape(1, 1, 1));
+        shapes.insert("b.png".to_owned(), shape(1, 2, 1));
+        shapes.insert("c.png".to_owned(), shape(2, 1, 1));
+        shapes.insert("d.png".to_owned(), shape(1, 1, 4));
+
+        let layout = Layout::pack(&shapes, 128).unwrap();
+        assert_eq!(layout.pages.len(), 1);
+
+        let rects = layout
+            .textures
+            .values()
+            .map(|(shape, placement)| {
+                let (width, height) = shape.cell();
+                (placement.x, placement.y, width * shape.frames, height)
+            })
+            .collect::<Vec<_>>();
+
+        for (i, a) in rects.iter().enumerate() {
+            for b in rects.iter().skip(i + 1) {
+                let apart =
+                    a.0 + a.2 <= b.0 || b.0 + b.2 <= a.0 || a.1 + a.3 <= b.1 || b.1 + b.3 <= a.1;
+                assert!(apart, "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn overflows_onto_new_pages() {
+        let mut shapes = HashMap::new();
+
+        for i in 0..5 {
+            shapes.insert(format!("{}.png", i), shape(1, 1, 1));
+        }
+
+        // four cells of 18 units fit on a 40 unit page, the fifth one starts another
+        let layout = Layout::pack(&shapes, 40).unwrap();
+        assert_eq!(layout.pages.len(), 2);
+        assert_eq!(layout.pages[0], (64, 64));
+        assert_eq!(layout.pages[1], (32, 32));
+    }
+
+    #[test]
+    fn ranges_carry_frames() {
+        let mut shapes = HashMap::new();
+        shapes.insert("water.png".to_owned(), shape(1, 1, 4));
+
+        let mut animations = HashMap::new();
+        animations.insert(
+            "water.png".to_owned(),
+            Animation {
+                frames: 4,
+                frame_time: 0.25,
+            },
+        );
+
+        let layout = Layout::pack(&shapes, 128).unwrap();
+        let uv = &layout.ranges(&animations)["water.png"];
+
+        assert_eq!(layout.pages[0], (128, 32));
+        assert_eq!(uv.animation, 1);
+        assert_eq!(uv.frames, 4);
+        assert_eq!(uv.start_u, 1.0 / 128.0);
+        assert_eq!(uv.end_u, 17.0 / 128.0);
+        assert_eq!(uv.frame_offset, 18.0 / 128.0);
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    fs::File,
+    str::FromStr,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use log::warn;
+use serde_json::Value;
+
+use crate::{constants::MUTES_FILE, network::models::messages};
+
+/// Player state keys used by the chat, persisted with the player's data
+pub const CHANNEL_STATE: &str = "channel";
+pub const TEAM_STATE: &str = "team";
+pub const IGNORED_STATE: &str = "ignored";
+
+/// Where a player's chat messages go
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Channel {
+    /// Players of the same world
+    #[default]
+    World,
+    /// Players of the same team, in any world
+    Team,
+    /// Players of every world
+    Global,
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Channel::World => "world",
+            Channel::Team => "team",
+            Channel::Global => "global",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Channel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "world" => Ok(Channel::World),
+            "team" => Ok(Channel::Team),
+            "global" => Ok(Channel::Global),
+            _ => Err(format!(
+                "Unknown channel \"{}\", try world, team or global.",
+                s
+            )),
+        }
+    }
+}
+
+/// Chat message leaving a world, delivered by the server once the world has ticked
+#[derive(Debug, Clone)]
+pub enum OutgoingChat {
+    /// To the members of `team` in every world, or to everyone if there's no team
+    Broadcast {
+        team: Option<String>,
+        sender_uuid: String,
+        message: messages::Message,
+    },
+    /// To the online player named `to`, wherever they are
+    Private {
+        sender_id: usize,
+        sender_uuid: String,
+        to: String,
+        message: messages::Message,
+    },
+}
+
+/// Resource of chat messages waiting to be picked up by the server
+pub type ChatOutbox = Vec<OutgoingChat>;
+
+/// Resource of the latest chat messages, replayed to players joining the world
+pub struct ChatHistory {
+    messages: VecDeque<messages::Message>,
+    capacity: usize,
+}
+
+impl ChatHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            messages: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Keep a message, forgetting the oldest one if full
+    pub fn push(&mut self, message: messages::Message) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.messages.len() >= self.capacity {
+            self.messages.pop_front();
+        }
+
+        self.messages.push_back(message);
+    }
+
+    /// Kept messages, oldest first
+    pub fn to_vec(&self) -> Vec<messages::Message> {
+        self.messages.iter().cloned().collect()
+    }
+}
+
+/// Resource limiting how many chat messages a player can send within a time window
+#[derive(Default)]
+pub struct ChatLimiter {
+    sent: HashMap<usize, VecDeque<Instant>>,
+}
+
+impl ChatLimiter {
+    /// Record a message from a player, returning false if the player is over the limit
+    pub fn allow(&mut self, player_id: usize, limit: usize, window: Duration) -> bool {
+        let now = Instant::now();
+        let sent = self.sent.entry(player_id).or_default();
+
+        while sent
+            .front()
+            .is_some_and(|instant| now.duration_since(*instant) > window)
+        {
+            sent.pop_front();
+        }
+
+        if sent.len() >= limit {
+            return false;
+        }
+
+        sent.push_back(now);
+        true
+    }
+
+    /// Drop what's known about a player that has left
+    pub fn forget(&mut self, player_id: usize) {
+        self.sent.remove(&player_id);
+    }
+}
+
+/// Mutes of every world, keyed by UUID and by address like bans, so that
+/// reconnecting as a new guest doesn't lift them
+#[derive(Debug, Default)]
+pub struct Mutes {
+    muted: HashMap<String, Value>,
+}
+
+/// Mutes shared by the server and all of its worlds
+pub type SharedMutes = Arc<RwLock<Mutes>>;
+
+impl Mutes {
+    /// Load the mutes still in effect
+    pub fn load() -> Self {
+        let muted: HashMap<String, Value> = match File::open(MUTES_FILE) {
+            Ok(file) => serde_json::from_reader(file).unwrap_or_else(|error| {
+                warn!("Corrupted mutes file {}: {}", MUTES_FILE, error);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        Self { muted }
+    }
+
+    fn save(&mut self) {
+        self.muted.retain(|_, value| is_muted(Some(value)));
+
+        let result = File::create(MUTES_FILE)
+            .map_err(|error| error.to_string())
+            .and_then(|file| serde_json::to_writer(file, &self.muted).map_err(|e| e.to_string()));
+
+        if let Err(error) = result {
+            warn!("Unable to save mutes to {}: {}", MUTES_FILE, error);
+        }
+    }
+
+    /// Whether any of the UUID or address of a player is muted
+    pub fn is_muted(&self, keys: &[&str]) -> bool {
+        keys.iter().any(|key| is_muted(self.muted.get(*key)))
+    }
+
+    /// Mute the UUID and address of a player, for `duration` or until unmuted
+    pub fn mute(&mut self, keys: &[&str], duration: Option<Duration>) {
+        for key in keys {
+            self.muted.insert((*key).to_owned(), mute_value(duration));
+        }
+
+        self.save();
+    }
+
+    pub fn unmute(&mut self, keys: &[&str]) {
+        for key in keys {
+            self.muted.remove(*key);
+        }
+
+        self.save();
+    }
+}
+
+/// Value of a mute, muting forever without a duration
+pub fn mute_value(duration: Option<Duration>) -> Value {
+    match duration {
+        Some(duration) => Value::from((now_millis() + duration.as_millis()) as u64),
+        None => Value::Bool(true),
+    }
+}
+
+/// Whether a mute value is still in effect
+pub fn is_muted(value: Option<&Value>) -> bool {
+    match value {
+        Some(Value::Bool(muted)) => *muted,
+        Some(Value::Number(until)) => until
+            .as_u64()
+            .is_some_and(|until| u128::from(until) > now_millis()),
+        _ => false,
+    }
+}
+
+fn now_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_messages_per_window() {
+        let mut limiter = ChatLimiter::default();
+        let window = Duration::from_secs(60);
+
+        assert!(limiter.allow(1, 2, window));
+        assert!(limiter.allow(1, 2, window));
+        assert!(!limiter.allow(1, 2, window));
+        assert!(limiter.allow(2, 2, window));
+
+        limiter.forget(1);
+        assert!(limiter.allow(1, 2, window));
+    }
+
+    #[test]
+    fn keeps_latest_history() {
+        let mut history = ChatHistory::new(2);
+
+        for text in ["a", "b", "c"].iter() {
+            history.push(messages::Message {
+                text: text.to_string(),
+                ..Default::default()
+            });
+        }
+
+        let texts = history
+            .to_vec()
+            .into_iter()
+            .map(|m| m.text)
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn mutes_expire() {
+        assert!(is_muted(Some(&mute_value(None))));
+        assert!(is_muted(Some(&mute_value(Some(Duration::from_secs(60))))));
+        assert!(!is_muted(Some(&Value::from(0))));
+        assert!(!is_muted(None));
+    }
+}
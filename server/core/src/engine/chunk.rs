@@ -213,6 +213,30 @@ impl Chunk {
         }
     }
 
+    /// Replace the block id of every voxel through `map`, keeping rotations and stages
+    ///
+    /// Returns whether any voxel changed, in which case the chunk needs saving.
+    pub fn remap_ids<F: Fn(u32) -> u32>(&mut self, map: F) -> bool {
+        let mut changed = false;
+
+        for voxel in self.voxels.data.iter_mut() {
+            let id = Blocks::extract_id(*voxel);
+            let new_id = map(id);
+
+            if new_id != id {
+                *voxel = Blocks::insert_id(*voxel, new_id);
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.needs_saving = true;
+            self.needs_propagation = true;
+        }
+
+        changed
+    }
+
     /// Save the chunk into a JSON compressed file
     pub fn save(&self) {
         let mut file = File::create(&self.file).expect("Could not create chunk file.");
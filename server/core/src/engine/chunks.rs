@@ -2,7 +2,7 @@ use hashbrown::{HashMap, HashSet};
 use std::{collections::VecDeque, path::PathBuf, sync::Arc};
 
 use crossbeam_channel::{unbounded, Receiver, Sender};
-use log::{debug, info};
+use log::{debug, info, warn};
 use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
 
 use crate::gen::{biomes::Biomes, blocks::BlockRotation};
@@ -11,6 +11,7 @@ use super::super::{
     constants::{LEVEL_SEED, VOXEL_NEIGHBORS},
     engine::{
         chunk::{Chunk, Meshes},
+        palette,
         registry::Registry,
         space::Space,
         world::WorldConfig,
@@ -48,6 +49,8 @@ pub struct Chunks {
     pub generating: HashSet<Vec2<i32>>,
     pub to_mesh: VecDeque<Vec2<i32>>,
     pub meshing: HashSet<Vec2<i32>>,
+    // chunks that were being meshed when the registry got reloaded
+    pub stale: HashSet<Vec2<i32>>,
     pub activities: VecDeque<Vec2<i32>>,
 
     pub config: Arc<WorldConfig>,
@@ -75,6 +78,16 @@ pub struct Chunks {
  */
 impl Chunks {
     pub fn new(world_name: &str, config: WorldConfig, registry: Registry) -> Self {
+        Chunks::with_biomes(world_name, config, registry, Arc::new(Biomes::default()))
+    }
+
+    /// Chunks of a world generating with the given biomes instead of the ones on disk
+    pub fn with_biomes(
+        world_name: &str,
+        config: WorldConfig,
+        registry: Registry,
+        biomes: Arc<Biomes>,
+    ) -> Self {
         let (gen_sender, gen_receiver) = unbounded();
         let gen_sender = Arc::new(gen_sender);
         let gen_receiver = Arc::new(gen_receiver);
@@ -95,6 +108,18 @@ impl Chunks {
                 "Storage for world \"{}\" is at \"./{}/{}\".",
                 world_name, config.chunk_root, world_name
             );
+
+            match palette::migrate(&root_folder, &chunk_folder, &config, &registry) {
+                Ok(0) => {}
+                Ok(count) => info!(
+                    "Migrated the block ids of {} chunks in world \"{}\".",
+                    count, world_name
+                ),
+                Err(error) => warn!(
+                    "Unable to migrate block ids of world \"{}\": {}",
+                    world_name, error
+                ),
+            }
         } else {
             info!("World \"{}\" is temporarily saved in memory.", world_name);
         }
@@ -107,12 +132,13 @@ impl Chunks {
             config: Arc::new(config),
             registry: Arc::new(registry.to_owned()),
             builder: Arc::new(Builder::new(registry, Noise::new(LEVEL_SEED))),
-            biomes: Arc::new(Biomes::default()),
+            biomes,
 
             to_generate: vec![],
             generating: HashSet::new(),
             to_mesh: VecDeque::new(),
             meshing: HashSet::new(),
+            stale: HashSet::new(),
             activities: VecDeque::new(),
 
             caching: false,
@@ -231,7 +257,12 @@ impl Chunks {
         }
 
         if let Ok(chunks) = self.mesh_receiver.try_recv() {
-            chunks.into_iter().for_each(|c| {
+            chunks.into_iter().for_each(|mut c| {
+                if self.stale.remove(&c.coords) {
+                    c.needs_propagation = true;
+                    c.is_dirty = true;
+                }
+
                 self.add_chunk(c);
             });
         }
@@ -243,6 +274,26 @@ impl Chunks {
         }
     }
 
+    /// Swap in reloaded block and biome definitions, remeshing every loaded chunk with them
+    pub fn reload(&mut self, registry: Registry, biomes: Arc<Biomes>) {
+        if self.config.save {
+            if let Err(error) = palette::save(&self.root_folder, &registry) {
+                warn!("Unable to save block palette: {}", error);
+            }
+        }
+
+        self.builder = Arc::new(Builder::new(registry.to_owned(), Noise::new(LEVEL_SEED)));
+        self.registry = Arc::new(registry);
+        self.biomes = biomes;
+
+        self.stale.extend(self.meshing.iter().cloned());
+
+        self.chunks.values_mut().for_each(|chunk| {
+            chunk.needs_propagation = true;
+            chunk.is_dirty = true;
+        });
+    }
+
     /// Getter for the count of internal chunks
     pub fn len(&self) -> usize {
         self.chunks.len()
@@ -950,6 +1001,54 @@ impl Chunks {
         voxel
     }
 
+    /// Whether a player can safely stand on the voxel at `vx, vy, vz`
+    ///
+    /// The voxel needs to be solid ground, with two free non-fluid voxels above it.
+    pub fn is_safe_spawn(&self, vx: i32, vy: i32, vz: i32) -> bool {
+        if vy <= 0 || vy + 2 >= self.config.max_height as i32 {
+            return false;
+        }
+
+        let ground = self
+            .registry
+            .get_block_by_id(self.get_voxel_by_voxel(vx, vy, vz));
+
+        if !ground.is_solid || ground.is_fluid || ground.is_plant {
+            return false;
+        }
+
+        (1..=2).all(|dy| {
+            let id = self.get_voxel_by_voxel(vx, vy + dy, vz);
+            !self.registry.is_fluid(id) && self.get_walkable_by_voxel(vx, vy + dy, vz)
+        })
+    }
+
+    /// Find a safe voxel to spawn on, searching outwards in rings from `vx, vz`
+    ///
+    /// Falls back to the top of the starting column if nothing safe is found within `radius`.
+    pub fn find_safe_spawn(&self, vx: i32, vz: i32, radius: i32) -> Vec3<i32> {
+        for r in 0..=radius {
+            for dx in -r..=r {
+                for dz in -r..=r {
+                    // only check the ring, inner columns have been checked already
+                    if dx.abs() != r && dz.abs() != r {
+                        continue;
+                    }
+
+                    let (x, z) = (vx + dx, vz + dz);
+
+                    let height = self.get_max_height(x, z) as i32;
+
+                    if self.is_safe_spawn(x, height, z) {
+                        return Vec3(x, height, z);
+                    }
+                }
+            }
+        }
+
+        Vec3(vx, self.get_max_height(vx, vz) as i32, vz)
+    }
+
     /// Add a chunk instance to self
     ///
     /// Removes existing chunks first.
@@ -1030,6 +1129,10 @@ impl Chunks {
         const BLUE: LightColor = LightColor::Blue;
         const NONE: LightColor = LightColor::None;
 
+        // whether light passes through the new block differently, though both let it through
+        let dims_differently = current_type.light_reduction != updated_type.light_reduction
+            || current_type.light_filter != updated_type.light_filter;
+
         // update light levels
         if !needs_propagation {
             if current_type.is_light {
@@ -1037,8 +1140,11 @@ impl Chunks {
                 Lights::global_remove_light(self, vx, vy, vz, false, &RED);
                 Lights::global_remove_light(self, vx, vy, vz, false, &GREEN);
                 Lights::global_remove_light(self, vx, vy, vz, false, &BLUE);
-            } else if current_type.is_transparent && !updated_type.is_transparent {
-                // remove light if solid block is placed
+            } else if current_type.is_transparent
+                && (!updated_type.is_transparent || dims_differently)
+            {
+                // remove light if solid block is placed, or if the light through it changes,
+                // refilling it from the neighbors through the new block
                 [false, true].iter().for_each(|&is_sunlight| {
                     if is_sunlight {
                         if self.get_sunlight(vx, vy, vz) != 0 {
@@ -1102,8 +1208,10 @@ impl Chunks {
                         &BLUE,
                     );
                 }
-            } else if updated_type.is_transparent && !current_type.is_transparent {
-                // solid block removed
+            } else if updated_type.is_transparent
+                && (!current_type.is_transparent || dims_differently)
+            {
+                // solid block removed, or light passes through differently
                 [false, true].iter().for_each(|&is_sunlight| {
                     let mut queue = VecDeque::<LightNode>::new();
                     let mut red_queue = VecDeque::<LightNode>::new();
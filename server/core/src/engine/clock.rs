@@ -2,9 +2,40 @@
 
 use std::time::SystemTime;
 
+/// Length of a full day/night cycle, in units of `time`
+pub const DAY_LENGTH: f32 = 2400.0;
+
+/// Times the sun is halfway up and halfway down, and how long rising or setting takes
+pub const SUNRISE: f32 = 600.0;
+pub const SUNSET: f32 = 1800.0;
+pub const SUN_CHANGE_SPAN: f32 = 200.0;
+
+/// Share of sunlight left at night
+pub const MIN_SUNLIGHT: f32 = 0.1;
+
+/// Share of sunlight at a time of day, the same curve clients light their sky with
+pub fn sunlight_intensity(time: f32) -> f32 {
+    let time = time.rem_euclid(DAY_LENGTH);
+    let half_span = SUN_CHANGE_SPAN / 2.0;
+
+    let intensity = if time < SUNRISE - half_span || time > SUNSET + half_span {
+        0.0
+    } else if time < SUNRISE + half_span {
+        (time - (SUNRISE - half_span)) / SUN_CHANGE_SPAN
+    } else if time > SUNSET - half_span {
+        1.0 - (time - (SUNSET - half_span)) / SUN_CHANGE_SPAN
+    } else {
+        1.0
+    };
+
+    intensity.max(MIN_SUNLIGHT)
+}
+
 pub struct Clock {
     pub time: f32,
     pub tick_speed: f32,
+    /// Stops the day/night cycle, keeping `time` where it is
+    pub locked: bool,
 
     pub tick: i32,
     pub delta: f32,
@@ -17,6 +48,7 @@ impl Clock {
         Self {
             time,
             tick_speed,
+            locked: false,
             tick: 0,
             delta: 0.0,
             prev_time: SystemTime::now(),
@@ -33,9 +65,18 @@ impl Clock {
         self.delta * 1000.0
     }
 
-    /// Set the time of clock
+    /// Set the time of clock, wrapped into a single day
     pub fn set_time(&mut self, time: f32) {
-        self.time = time;
+        self.time = time.rem_euclid(DAY_LENGTH);
+    }
+
+    /// Tick speed clients should run at, zero while the cycle is locked
+    pub fn effective_tick_speed(&self) -> f32 {
+        if self.locked {
+            0.0
+        } else {
+            self.tick_speed
+        }
     }
 
     /// Set the tick speed of clock
@@ -58,7 +99,24 @@ impl Clock {
 
         self.prev_time = now;
 
-        self.time = (self.time + self.tick_speed * self.delta) % 2400.0;
+        if !self.locked {
+            self.time = (self.time + self.tick_speed * self.delta) % DAY_LENGTH;
+        }
+
         self.tick += 1;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_the_sun() {
+        assert_eq!(sunlight_intensity(0.0), MIN_SUNLIGHT);
+        assert_eq!(sunlight_intensity(SUNRISE), 0.5);
+        assert_eq!(sunlight_intensity(DAY_LENGTH / 2.0), 1.0);
+        assert_eq!(sunlight_intensity(SUNSET), 0.5);
+        assert_eq!(sunlight_intensity(SUNSET + SUN_CHANGE_SPAN), MIN_SUNLIGHT);
+    }
+}
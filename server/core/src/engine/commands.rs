@@ -0,0 +1,926 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Arc,
+    time::Duration,
+};
+
+use server_common::vec::Vec3;
+
+use super::{
+    chat::Channel,
+    clock::Clock,
+    permissions::{Permission, Role},
+    players::Players,
+    registry::Registry,
+    weather::Weather,
+    world::World,
+};
+
+/// What a command prints back to its sender, or why it failed
+pub type CommandResult = Result<Option<String>, String>;
+
+/// Function run for a command, with the sender's id and the parsed arguments
+pub type CommandHandler = Arc<dyn Fn(&mut World, usize, &Args) -> CommandResult + Send + Sync>;
+
+/// Types of arguments a command can take
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArgKind {
+    Int,
+    Float,
+    /// Three numbers `x y z`, each of which can be relative to the sender with `~`
+    Coords,
+    /// Name of a player in the same world
+    Player,
+    /// Block name (spaces as underscores) or id, checked against the registry
+    Block,
+    Word,
+    /// Everything left in the command
+    Text,
+}
+
+impl ArgKind {
+    /// Number of words this argument takes up, `None` if it takes the rest
+    fn width(&self) -> Option<usize> {
+        match self {
+            ArgKind::Coords => Some(3),
+            ArgKind::Text => None,
+            _ => Some(1),
+        }
+    }
+}
+
+/// A parsed argument
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgValue {
+    Int(i32),
+    Float(f32),
+    Coords(Vec3<f32>),
+    Player(usize),
+    Block(u32),
+    Word(String),
+}
+
+/// Declaration of a single command argument
+#[derive(Debug, Clone)]
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub kind: ArgKind,
+    pub optional: bool,
+}
+
+impl fmt::Display for ArgSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.kind {
+            ArgKind::Coords => "x y z".to_owned(),
+            _ => self.name.to_owned(),
+        };
+
+        if self.optional {
+            write!(f, "[{}]", name)
+        } else {
+            write!(f, "<{}>", name)
+        }
+    }
+}
+
+/// Arguments of a command, by name
+#[derive(Debug, Default)]
+pub struct Args {
+    values: HashMap<&'static str, ArgValue>,
+}
+
+impl Args {
+    pub fn has(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    pub fn int(&self, name: &str) -> Option<i32> {
+        match self.values.get(name) {
+            Some(ArgValue::Int(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn float(&self, name: &str) -> Option<f32> {
+        match self.values.get(name) {
+            Some(ArgValue::Float(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn coords(&self, name: &str) -> Option<Vec3<f32>> {
+        match self.values.get(name) {
+            Some(ArgValue::Coords(value)) => Some(value.to_owned()),
+            _ => None,
+        }
+    }
+
+    pub fn player(&self, name: &str) -> Option<usize> {
+        match self.values.get(name) {
+            Some(ArgValue::Player(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn block(&self, name: &str) -> Option<u32> {
+        match self.values.get(name) {
+            Some(ArgValue::Block(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn word(&self, name: &str) -> Option<&str> {
+        match self.values.get(name) {
+            Some(ArgValue::Word(value)) => Some(value.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// World state arguments are resolved against
+pub struct ParseContext<'a> {
+    /// Position `~` coordinates are relative to
+    pub origin: Vec3<f32>,
+    pub players: &'a Players,
+    pub registry: &'a Registry,
+}
+
+/// A chat command, registered with `World::register_command`
+#[derive(Clone)]
+pub struct Command {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub description: String,
+    /// Alternative argument lists, the first one that parses is used
+    pub signatures: Vec<Vec<ArgSpec>>,
+    pub permission: Option<Permission>,
+    pub handler: CommandHandler,
+}
+
+impl Command {
+    pub fn new<F>(name: &str, description: &str, handler: F) -> Self
+    where
+        F: Fn(&mut World, usize, &Args) -> CommandResult + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_owned(),
+            aliases: vec![],
+            description: description.to_owned(),
+            signatures: vec![vec![]],
+            permission: None,
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// Add a required argument. Required arguments go before optional ones.
+    pub fn arg(mut self, name: &'static str, kind: ArgKind) -> Self {
+        self.current().push(ArgSpec {
+            name,
+            kind,
+            optional: false,
+        });
+        self
+    }
+
+    /// Add an optional argument
+    pub fn optional(mut self, name: &'static str, kind: ArgKind) -> Self {
+        self.current().push(ArgSpec {
+            name,
+            kind,
+            optional: true,
+        });
+        self
+    }
+
+    /// Start an alternative argument list, such as `/tp <x y z>` or `/tp <player>`
+    pub fn or(mut self) -> Self {
+        self.signatures.push(vec![]);
+        self
+    }
+
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.aliases.push(alias.to_owned());
+        self
+    }
+
+    pub fn permission(mut self, permission: Permission) -> Self {
+        self.permission = Some(permission);
+        self
+    }
+
+    /// Whether a role may run this command
+    pub fn allowed(&self, role: Role) -> bool {
+        self.permission
+            .is_none_or(|permission| role.allows(permission))
+    }
+
+    /// Usage lines, one per argument list, such as `/tp <player> [x y z]`
+    pub fn usages(&self) -> Vec<String> {
+        self.signatures
+            .iter()
+            .map(|signature| {
+                let mut usage = format!("/{}", self.name);
+
+                for arg in signature.iter() {
+                    usage.push_str(&format!(" {}", arg));
+                }
+
+                usage
+            })
+            .collect()
+    }
+
+    /// Parse the words following the command name
+    ///
+    /// If no argument list fits, the error of the list taking as many words as given is
+    /// returned, or else the error of the first list.
+    pub fn parse(&self, words: &[&str], context: &ParseContext) -> Result<Args, String> {
+        let mut errors = vec![];
+
+        for signature in self.signatures.iter() {
+            match parse_signature(signature, words, context) {
+                Ok(args) => return Ok(args),
+                Err(reason) => errors.push((signature, reason)),
+            }
+        }
+
+        let fits = |signature: &Vec<ArgSpec>| {
+            let widths = signature.iter().map(|spec| spec.kind.width());
+            let required = signature
+                .iter()
+                .filter(|spec| !spec.optional)
+                .map(|spec| spec.kind.width().unwrap_or(1))
+                .sum::<usize>();
+
+            match widths.sum::<Option<usize>>() {
+                Some(total) => (required..=total).contains(&words.len()),
+                None => words.len() >= required,
+            }
+        };
+
+        let reason = errors
+            .iter()
+            .find(|(signature, _)| fits(signature))
+            .or_else(|| errors.first())
+            .map(|(_, reason)| reason.to_owned())
+            .unwrap_or_default();
+
+        Err(format!("{} Usage: {}", reason, self.usages().join(" or ")))
+    }
+
+    fn current(&mut self) -> &mut Vec<ArgSpec> {
+        self.signatures.last_mut().unwrap()
+    }
+}
+
+fn parse_signature(
+    signature: &[ArgSpec],
+    words: &[&str],
+    context: &ParseContext,
+) -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut rest = words;
+
+    for spec in signature.iter() {
+        if rest.is_empty() {
+            if spec.optional {
+                break;
+            }
+
+            return Err(format!("Missing {}.", spec));
+        }
+
+        let width = spec.kind.width().unwrap_or(rest.len());
+
+        if rest.len() < width {
+            return Err(format!("Missing {}.", spec));
+        }
+
+        let value = parse_arg(spec.kind, &rest[..width], context)?;
+
+        args.values.insert(spec.name, value);
+        rest = &rest[width..];
+    }
+
+    if !rest.is_empty() {
+        return Err("Too many arguments.".to_owned());
+    }
+
+    Ok(args)
+}
+
+/// Resource of all chat commands of a world
+#[derive(Default)]
+pub struct Commands {
+    commands: BTreeMap<String, Command>,
+    aliases: HashMap<String, String>,
+}
+
+impl Commands {
+    /// Register a command, replacing any command or alias of the same name
+    pub fn register(&mut self, command: Command) {
+        command.aliases.iter().for_each(|alias| {
+            self.aliases
+                .insert(alias.to_owned(), command.name.to_owned());
+        });
+
+        self.aliases.remove(&command.name);
+        self.commands.insert(command.name.to_owned(), command);
+    }
+
+    /// Find a command by its name or one of its aliases
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        let name = name.to_lowercase();
+        let name = self.aliases.get(&name).unwrap_or(&name);
+
+        self.commands.get(name)
+    }
+
+    /// Commands a role is allowed to run, sorted by name
+    pub fn available(&self, role: Role) -> Vec<&Command> {
+        self.commands
+            .values()
+            .filter(|command| command.allowed(role))
+            .collect()
+    }
+
+    /// Generated `/help` text, listing what a role can run
+    pub fn help(&self, role: Role) -> String {
+        self.available(role)
+            .into_iter()
+            .flat_map(|command| {
+                command
+                    .usages()
+                    .into_iter()
+                    .map(move |usage| format!("{} - {}", usage, command.description))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Suggestions for the last word of a partially typed command
+    pub fn complete(&self, input: &str, role: Role, context: &ParseContext) -> Vec<String> {
+        let input = input.strip_prefix('/').unwrap_or(input);
+
+        let mut words = input.split_whitespace().collect::<Vec<_>>();
+
+        // a trailing space means a new word is being started
+        if input.is_empty() || input.ends_with(char::is_whitespace) {
+            words.push("");
+        }
+
+        let partial = words.pop().unwrap_or_default().to_lowercase();
+
+        if words.is_empty() {
+            return self
+                .available(role)
+                .into_iter()
+                .filter(|command| command.name.starts_with(&partial))
+                .map(|command| format!("/{}", command.name))
+                .collect();
+        }
+
+        let command = match self.get(words[0]) {
+            Some(command) if command.allowed(role) => command,
+            _ => return vec![],
+        };
+
+        let mut suggestions = vec![];
+
+        for signature in command.signatures.iter() {
+            let mut index = words.len() - 1;
+            let mut kind = None;
+
+            for spec in signature.iter() {
+                match spec.kind.width() {
+                    Some(width) if index >= width => index -= width,
+                    _ => {
+                        kind = Some(spec.kind);
+                        break;
+                    }
+                }
+            }
+
+            let mut candidates = match kind {
+                Some(ArgKind::Player) => context
+                    .players
+                    .values()
+                    .filter_map(|player| player.name.to_owned())
+                    .collect(),
+                Some(ArgKind::Block) => context
+                    .registry
+                    .block_names()
+                    .into_iter()
+                    .map(|name| name.replace(' ', "_").to_lowercase())
+                    .collect(),
+                Some(ArgKind::Coords) => vec!["~".to_owned()],
+                _ => vec![],
+            };
+
+            suggestions.append(&mut candidates);
+        }
+
+        suggestions.retain(|suggestion| suggestion.to_lowercase().starts_with(&partial));
+        suggestions.sort();
+        suggestions.dedup();
+        suggestions
+    }
+}
+
+fn parse_arg(kind: ArgKind, words: &[&str], context: &ParseContext) -> Result<ArgValue, String> {
+    let word = words[0];
+
+    match kind {
+        ArgKind::Int => word
+            .parse::<i32>()
+            .map(ArgValue::Int)
+            .map_err(|_| format!("\"{}\" is not a whole number.", word)),
+        ArgKind::Float => match word.parse::<f32>() {
+            Ok(value) if value.is_finite() => Ok(ArgValue::Float(value)),
+            _ => Err(format!("\"{}\" is not a number.", word)),
+        },
+        ArgKind::Coords => {
+            let Vec3(ox, oy, oz) = context.origin;
+
+            Ok(ArgValue::Coords(Vec3(
+                parse_coordinate(words[0], ox)?,
+                parse_coordinate(words[1], oy)?,
+                parse_coordinate(words[2], oz)?,
+            )))
+        }
+        ArgKind::Player => context
+            .players
+            .iter()
+            .find(|(_, player)| player.name.as_deref() == Some(word))
+            .map(|(&id, _)| ArgValue::Player(id))
+            .ok_or_else(|| format!("No player named \"{}\" in this world.", word)),
+        ArgKind::Block => {
+            let id = match word.parse::<u32>() {
+                Ok(id) if context.registry.has_type(id) => Some(id),
+                Ok(_) => None,
+                Err(_) => context.registry.find_id_by_name(&word.replace('_', " ")),
+            };
+
+            id.map(ArgValue::Block)
+                .ok_or_else(|| format!("Unknown block \"{}\".", word))
+        }
+        ArgKind::Word => Ok(ArgValue::Word(word.to_owned())),
+        ArgKind::Text => Ok(ArgValue::Word(words.join(" "))),
+    }
+}
+
+/// Parse a coordinate, `~` and `~n` being relative to `origin`
+fn parse_coordinate(word: &str, origin: f32) -> Result<f32, String> {
+    let (relative, number) = match word.strip_prefix('~') {
+        Some("") => return Ok(origin),
+        Some(number) => (true, number),
+        None => (false, word),
+    };
+
+    match number.parse::<f32>() {
+        Ok(value) if value.is_finite() && relative => Ok(origin + value),
+        Ok(value) if value.is_finite() => Ok(value),
+        _ => Err(format!("\"{}\" is not a coordinate.", word)),
+    }
+}
+
+/// Voxel to stand on for a position given in voxel units
+fn standing_voxel(position: &Vec3<f32>) -> Vec3<i32> {
+    let Vec3(x, y, z) = position;
+    Vec3(x.floor() as i32, y.floor() as i32 - 1, z.floor() as i32)
+}
+
+/// Name of a player in the world, empty if unknown
+fn player_name(world: &World, player_id: usize) -> String {
+    world
+        .read_resource::<Players>()
+        .get(&player_id)
+        .and_then(|player| player.name.to_owned())
+        .unwrap_or_default()
+}
+
+/// Shared by `/ignore` and `/unignore`
+fn set_ignored(world: &mut World, id: usize, args: &Args, ignored: bool) -> CommandResult {
+    let target = args.player("player").unwrap_or_default();
+
+    if target == id {
+        return Err("You can't ignore yourself.".to_owned());
+    }
+
+    let uuid = world
+        .read_resource::<Players>()
+        .get(&target)
+        .map(|player| player.identity.uuid.to_owned())
+        .ok_or_else(|| "Player is not in this world.".to_owned())?;
+
+    world.set_ignored(id, &uuid, ignored);
+
+    let name = player_name(world, target);
+    if ignored {
+        Ok(Some(format!("Ignoring {}.", name)))
+    } else {
+        Ok(Some(format!("No longer ignoring {}.", name)))
+    }
+}
+
+/// Commands every world comes with
+pub fn builtin_commands() -> Vec<Command> {
+    vec![
+        Command::new(
+            "help",
+            "List commands, or show how to use one",
+            |world, id, args| {
+                let role = world.role(id).unwrap_or(Role::Guest);
+                let commands = world.read_resource::<Commands>();
+
+                match args.word("command") {
+                    Some(name) => match commands.get(name) {
+                        Some(command) if command.allowed(role) => Ok(Some(format!(
+                            "{} - {}",
+                            command.usages().join(" or "),
+                            command.description
+                        ))),
+                        _ => Err(format!("Unknown command: /{}", name)),
+                    },
+                    None => Ok(Some(commands.help(role))),
+                }
+            },
+        )
+        .optional("command", ArgKind::Word),
+        Command::new("save", "Save the world", |world, _, _| {
+            world.save();
+            Ok(Some("World has been saved.".to_owned()))
+        })
+        .permission(Permission::Save),
+        Command::new("summon", "Summon a test entity", |world, id, _| {
+            world.test_entity(id)?;
+            Ok(Some("Summoned a test entity.".to_owned()))
+        })
+        .permission(Permission::Summon),
+        Command::new(
+            "setspawn",
+            "Set the spawn to where you stand",
+            |world, id, _| {
+                let Vec3(x, y, z) = world
+                    .set_spawn(id)
+                    .ok_or_else(|| "Unable to set the spawn.".to_owned())?;
+
+                Ok(Some(format!("Spawn set to {} {} {}.", x, y, z)))
+            },
+        )
+        .permission(Permission::SetSpawn),
+        Command::new(
+            "role",
+            "Show or change a player's role",
+            |world, _, args| {
+                let id = args.player("player").unwrap_or_default();
+                let name = world
+                    .read_resource::<Players>()
+                    .get(&id)
+                    .and_then(|player| player.name.to_owned())
+                    .unwrap_or_default();
+
+                match args.word("role") {
+                    Some(role) => {
+                        let role = role.parse::<Role>()?;
+                        world.set_role(id, role)?;
+
+                        Ok(Some(format!("{} is now a {}.", name, role)))
+                    }
+                    None => {
+                        let role = world.role(id).unwrap_or(Role::Guest);
+                        Ok(Some(format!("{} is a {}.", name, role)))
+                    }
+                }
+            },
+        )
+        .arg("player", ArgKind::Player)
+        .optional("role", ArgKind::Word)
+        .permission(Permission::ManageRoles),
+        Command::new("tp", "Teleport players", |world, id, args| {
+            let player = args.player("player");
+            let position = args.coords("position").map(|p| standing_voxel(&p));
+
+            let locate = |player_id: usize| {
+                world
+                    .player_voxel(player_id)
+                    .ok_or_else(|| "Player is not in this world.".to_owned())
+            };
+
+            let (target, destination) = match (player, args.player("destination"), position) {
+                (target, _, Some(voxel)) => (target.unwrap_or(id), voxel),
+                (Some(target), Some(destination), None) => (target, locate(destination)?),
+                // `/tp <player>` brings the sender to the player
+                (Some(destination), None, None) => (id, locate(destination)?),
+                (None, _, None) => return Err("Nowhere to teleport to.".to_owned()),
+            };
+
+            world.teleport_player(target, &destination)?;
+
+            let Vec3(x, y, z) = destination;
+            Ok(Some(format!("Teleported to {} {} {}.", x, y, z)))
+        })
+        .alias("teleport")
+        .arg("position", ArgKind::Coords)
+        .or()
+        .arg("player", ArgKind::Player)
+        .or()
+        .arg("player", ArgKind::Player)
+        .arg("destination", ArgKind::Player)
+        .or()
+        .arg("player", ArgKind::Player)
+        .arg("position", ArgKind::Coords)
+        .permission(Permission::Teleport),
+        Command::new(
+            "time",
+            "Query, set or lock the time of day",
+            |world, _, args| {
+                let action = args.word("action").unwrap_or_default();
+                let value = args.word("value");
+
+                let parse_time = |value: Option<&str>| match value {
+                    Some("day") => Ok(600.0),
+                    Some("noon") => Ok(1200.0),
+                    Some("night") => Ok(1800.0),
+                    Some("midnight") => Ok(0.0),
+                    Some(value) => match value.parse::<f32>() {
+                        Ok(time) if time.is_finite() => Ok(time),
+                        _ => Err(format!("\"{}\" is not a time.", value)),
+                    },
+                    None => Err(
+                        "Missing a time. Usage: /time set <time|day|noon|night|midnight>"
+                            .to_owned(),
+                    ),
+                };
+
+                match action {
+                    "query" => {}
+                    "set" => world.set_time(parse_time(value)?),
+                    "add" => {
+                        let time = world.read_resource::<Clock>().time;
+                        world.set_time(time + parse_time(value)?);
+                    }
+                    "lock" => world.set_time_locked(true),
+                    "unlock" => world.set_time_locked(false),
+                    _ => return Err("Usage: /time <query|set|add|lock|unlock> [time]".to_owned()),
+                }
+
+                let clock = world.read_resource::<Clock>();
+                let locked = if clock.locked { ", locked" } else { "" };
+
+                Ok(Some(format!("Time is {:.0}{}.", clock.time, locked)))
+            },
+        )
+        .arg("action", ArgKind::Word)
+        .optional("value", ArgKind::Word)
+        .permission(Permission::Configure),
+        Command::new(
+            "tickspeed",
+            "Query or set how fast time passes",
+            |world, _, args| {
+                if let Some(tick_speed) = args.float("speed") {
+                    if tick_speed < 0.0 {
+                        return Err("Tick speed can't be negative.".to_owned());
+                    }
+
+                    world.set_tick_speed(tick_speed);
+                }
+
+                let tick_speed = world.read_resource::<Clock>().tick_speed;
+                Ok(Some(format!("Tick speed is {}.", tick_speed)))
+            },
+        )
+        .optional("speed", ArgKind::Float)
+        .permission(Permission::Configure),
+        Command::new(
+            "weather",
+            "Query or change the weather",
+            |world, _, args| {
+                if let Some(weather) = args.word("weather") {
+                    world.set_weather(weather.parse::<Weather>()?);
+                }
+
+                let weather = *world.read_resource::<Weather>();
+                Ok(Some(format!("Weather is {}.", weather)))
+            },
+        )
+        .optional("weather", ArgKind::Word)
+        .permission(Permission::Configure),
+        Command::new("spawn", "Go back to the spawn", |world, id, _| {
+            let spawn = world.spawn_point();
+            world.teleport_player(id, &spawn)?;
+
+            Ok(None)
+        }),
+        Command::new("goto", "Go to another world", |world, id, args| {
+            let name = args.word("world").unwrap_or_default();
+
+            if name == world.name {
+                return Err(format!("You are already in \"{}\".", name));
+            }
+
+            let voxel = args.coords("position").map(|Vec3(x, y, z)| {
+                Vec3(x.floor() as i32, y.floor() as i32 - 1, z.floor() as i32)
+            });
+
+            world.transfer_player(id, name, voxel);
+
+            Ok(None)
+        })
+        .arg("world", ArgKind::Word)
+        .optional("position", ArgKind::Coords)
+        .permission(Permission::Teleport),
+        Command::new(
+            "world",
+            "Show which world you are in, or go to another one",
+            |world, id, args| match args.word("world") {
+                Some(name) if name == world.name => {
+                    Err(format!("You are already in \"{}\".", name))
+                }
+                // going to another world is a teleport, like /goto
+                Some(_) if !world.has_permission(id, Permission::Teleport) => Err(format!(
+                    "You don't have permission to {}.",
+                    Permission::Teleport
+                )),
+                Some(name) => {
+                    world.transfer_player(id, name, None);
+                    Ok(None)
+                }
+                None => Ok(Some(format!("You are in \"{}\".", world.name))),
+            },
+        )
+        .optional("world", ArgKind::Word),
+        Command::new(
+            "pack",
+            "Show the texture packs, or switch to another one",
+            |world, id, args| match args.word("pack") {
+                Some(pack) => {
+                    world.set_texture_pack(id, pack)?;
+                    Ok(None)
+                }
+                None => Ok(Some(format!(
+                    "Using \"{}\", available packs: {}.",
+                    world.texture_pack(id),
+                    world.texture_packs().join(", ")
+                ))),
+            },
+        )
+        .optional("pack", ArgKind::Word),
+        Command::new("msg", "Privately message a player", |world, id, args| {
+            let to = args.word("player").unwrap_or_default();
+            let body = args.word("message").unwrap_or_default();
+
+            world.send_private(id, to, body)?;
+
+            Ok(Some(format!("To {}: {}", to, body)))
+        })
+        .alias("tell")
+        .alias("w")
+        .arg("player", ArgKind::Word)
+        .arg("message", ArgKind::Text),
+        Command::new(
+            "channel",
+            "Show or change where your messages go",
+            |world, id, args| {
+                if let Some(channel) = args.word("channel") {
+                    let channel = channel.parse::<Channel>()?;
+
+                    if channel == Channel::Team && world.team(id).is_none() {
+                        return Err("You are not in a team, join one with /team <name>.".to_owned());
+                    }
+
+                    world.set_channel(id, channel);
+                }
+
+                Ok(Some(format!(
+                    "You are talking in {} chat.",
+                    world.channel(id)
+                )))
+            },
+        )
+        .optional("channel", ArgKind::Word),
+        Command::new(
+            "team",
+            "Join or leave a team",
+            |world, id, args| match args.word("team") {
+                Some(team) if team.eq_ignore_ascii_case("leave") => {
+                    world.set_team(id, None);
+
+                    if world.channel(id) == Channel::Team {
+                        world.set_channel(id, Channel::World);
+                    }
+
+                    Ok(Some("You left your team.".to_owned()))
+                }
+                Some(team) => {
+                    world.set_team(id, Some(team));
+                    Ok(Some(format!(
+                        "You joined team {}, talk to it with /channel team.",
+                        team.to_lowercase()
+                    )))
+                }
+                None => match world.team(id) {
+                    Some(team) => Ok(Some(format!("You are in team {}.", team))),
+                    None => Ok(Some("You are not in a team.".to_owned())),
+                },
+            },
+        )
+        .optional("team", ArgKind::Word),
+        Command::new(
+            "mute",
+            "Stop a player from chatting, optionally for some minutes",
+            |world, _, args| {
+                let target = args.player("player").unwrap_or_default();
+                let minutes = args.int("minutes");
+
+                if minutes.is_some_and(|minutes| minutes <= 0) {
+                    return Err("Minutes should be positive.".to_owned());
+                }
+
+                let duration = minutes.map(|minutes| Duration::from_secs(minutes as u64 * 60));
+                world.mute_player(target, duration);
+
+                let name = player_name(world, target);
+                match minutes {
+                    Some(minutes) => Ok(Some(format!("Muted {} for {} minutes.", name, minutes))),
+                    None => Ok(Some(format!("Muted {}.", name))),
+                }
+            },
+        )
+        .arg("player", ArgKind::Player)
+        .optional("minutes", ArgKind::Int)
+        .permission(Permission::Mute),
+        Command::new(
+            "unmute",
+            "Let a muted player chat again",
+            |world, _, args| {
+                let target = args.player("player").unwrap_or_default();
+                world.unmute_player(target);
+
+                Ok(Some(format!("Unmuted {}.", player_name(world, target))))
+            },
+        )
+        .arg("player", ArgKind::Player)
+        .permission(Permission::Mute),
+        Command::new(
+            "ignore",
+            "Hide a player's messages from you",
+            |world, id, args| set_ignored(world, id, args, true),
+        )
+        .arg("player", ArgKind::Player),
+        Command::new(
+            "unignore",
+            "Show a player's messages again",
+            |world, id, args| set_ignored(world, id, args, false),
+        )
+        .arg("player", ArgKind::Player),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_relative_coordinates() {
+        assert_eq!(parse_coordinate("~", 4.5), Ok(4.5));
+        assert_eq!(parse_coordinate("~-2", 4.5), Ok(2.5));
+        assert_eq!(parse_coordinate("10", 4.5), Ok(10.0));
+        assert!(parse_coordinate("~abc", 4.5).is_err());
+        assert!(parse_coordinate("NaN", 4.5).is_err());
+    }
+
+    #[test]
+    fn looks_up_commands_by_alias() {
+        let mut commands = Commands::default();
+        commands.register(Command::new("teleport", "", |_, _, _| Ok(None)).alias("tp"));
+
+        assert!(commands.get("TP").is_some());
+        assert!(commands.get("teleport").is_some());
+        assert!(commands.get("t").is_none());
+    }
+
+    #[test]
+    fn generates_usage_and_help() {
+        let mut commands = Commands::default();
+        commands.register(
+            Command::new("give", "Give a block", |_, _, _| Ok(None))
+                .arg("block", ArgKind::Block)
+                .optional("count", ArgKind::Int),
+        );
+        commands.register(
+            Command::new("save", "Save", |_, _, _| Ok(None)).permission(Permission::Save),
+        );
+
+        assert_eq!(
+            commands.get("give").unwrap().usages(),
+            vec!["/give <block> [count]"]
+        );
+        assert_eq!(
+            commands.help(Role::Guest),
+            "/give <block> [count] - Give a block"
+        );
+        assert_eq!(commands.available(Role::Operator).len(), 2);
+    }
+}
@@ -1,7 +1,9 @@
 use hashbrown::HashMap;
 
 use std::fs::{self, File};
+use std::path::Path;
 
+use serde_json::{json, Value};
 use server_utils::json;
 
 use super::{
@@ -9,32 +11,26 @@ use super::{
     world::{WorldConfig, WorldMeta},
 };
 
+pub type WorldDefinition = (WorldMeta, WorldConfig);
+
 pub struct Configs;
 
 impl Configs {
-    pub fn load_worlds(path: &str) -> (HashMap<String, (WorldMeta, WorldConfig)>, Registry) {
-        let worlds_json: serde_json::Value =
-            serde_json::from_reader(File::open(path).unwrap()).unwrap();
-
-        let world_default = &worlds_json["shared"];
+    /// Load every world defined in `worlds.json` or created at runtime in `created`,
+    /// along with the registry they share
+    pub fn load_worlds(path: &str, created: &str) -> (HashMap<String, WorldDefinition>, Registry) {
+        let packs = Self::load_packs();
+        let worlds_json = Self::read(path).unwrap();
+        let created_json = Self::read_created(created).unwrap();
 
         let mut map = HashMap::new();
 
-        let packs = fs::read_dir("./assets/textures/packs/").unwrap();
-        let packs = packs
-            .flatten()
-            .map(|e| e.file_name().into_string().unwrap())
-            .collect::<Vec<_>>();
-
-        for world_json in worlds_json["worlds"].as_array().unwrap() {
-            let mut world_json = world_json.clone();
-            json::merge(&mut world_json, world_default, false);
-
-            let mut meta: WorldMeta = serde_json::from_value(world_json.clone()).unwrap();
-            let config: WorldConfig = serde_json::from_value(world_json).unwrap();
-
-            meta.packs = packs.clone();
-
+        for world_json in Self::worlds(&worlds_json)
+            .unwrap()
+            .iter()
+            .chain(Self::worlds(&created_json).unwrap())
+        {
+            let (meta, config) = Self::parse(world_json, &worlds_json["shared"], &packs).unwrap();
             map.insert(meta.name.to_owned(), (meta, config));
         }
 
@@ -46,4 +42,150 @@ impl Configs {
 
         (map, registry)
     }
+
+    /// Re-read the definition of a single world, `None` if it's not defined anymore
+    pub fn load_world(
+        path: &str,
+        created: &str,
+        name: &str,
+    ) -> Result<Option<WorldDefinition>, String> {
+        let worlds_json = Self::read(path)?;
+        let created_json = Self::read_created(created)?;
+
+        let world_json = Self::find(&worlds_json, name).or_else(|| Self::find(&created_json, name));
+
+        match world_json {
+            Some(world_json) => {
+                let packs = Self::load_packs();
+                Self::parse(world_json, &worlds_json["shared"], &packs).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Define a new world in `created`, leaving the shipped `worlds.json` untouched,
+    /// failing if the name is taken
+    pub fn add_world(
+        path: &str,
+        created: &str,
+        world_json: Value,
+    ) -> Result<WorldDefinition, String> {
+        let worlds_json = Self::read(path)?;
+        let mut created_json = Self::read_created(created)?;
+
+        let name = world_json["name"]
+            .as_str()
+            .ok_or_else(|| "World needs a name.".to_owned())?;
+
+        if Self::find(&worlds_json, name).is_some() || Self::find(&created_json, name).is_some() {
+            return Err(format!("World already exists: {}", name));
+        }
+
+        let packs = Self::load_packs();
+        let definition = Self::parse(&world_json, &worlds_json["shared"], &packs)?;
+
+        created_json["worlds"]
+            .as_array_mut()
+            .ok_or_else(|| "Malformed worlds file.".to_owned())?
+            .push(world_json);
+
+        Self::write(created, &created_json)?;
+
+        Ok(definition)
+    }
+
+    /// Whether a world is one of the worlds shipped in `worlds.json`, which are only
+    /// ever removed by hand
+    pub fn is_shipped(path: &str, name: &str) -> Result<bool, String> {
+        Ok(Self::find(&Self::read(path)?, name).is_some())
+    }
+
+    /// Remove the definition of a world created at runtime
+    pub fn remove_world(created: &str, name: &str) -> Result<(), String> {
+        let mut created_json = Self::read_created(created)?;
+
+        let worlds = created_json["worlds"]
+            .as_array_mut()
+            .ok_or_else(|| "Malformed worlds file.".to_owned())?;
+
+        let count = worlds.len();
+        worlds.retain(|world_json| world_json["name"].as_str() != Some(name));
+
+        if worlds.len() == count {
+            return Err(format!("World not found: {}", name));
+        }
+
+        Self::write(created, &created_json)
+    }
+
+    /// Rebuild the registry from the block definitions and texture packs on disk
+    pub fn load_registry() -> Result<Registry, String> {
+        Registry::load(Self::load_packs())
+    }
+
+    fn read(path: &str) -> Result<Value, String> {
+        let file = File::open(path).map_err(|error| error.to_string())?;
+        serde_json::from_reader(file).map_err(|error| error.to_string())
+    }
+
+    /// Read the worlds created at runtime, none if there's no such file yet
+    fn read_created(path: &str) -> Result<Value, String> {
+        if !Path::new(path).exists() {
+            return Ok(json!({ "worlds": [] }));
+        }
+
+        Self::read(path)
+    }
+
+    fn write(path: &str, worlds_json: &Value) -> Result<(), String> {
+        if let Some(folder) = Path::new(path).parent() {
+            fs::create_dir_all(folder).map_err(|error| error.to_string())?;
+        }
+
+        let file = File::create(path).map_err(|error| error.to_string())?;
+        serde_json::to_writer_pretty(file, worlds_json).map_err(|error| error.to_string())
+    }
+
+    fn worlds(worlds_json: &Value) -> Result<&Vec<Value>, String> {
+        worlds_json["worlds"]
+            .as_array()
+            .ok_or_else(|| "Malformed worlds file.".to_owned())
+    }
+
+    fn find<'a>(worlds_json: &'a Value, name: &str) -> Option<&'a Value> {
+        Self::worlds(worlds_json)
+            .ok()?
+            .iter()
+            .find(|world_json| world_json["name"].as_str() == Some(name))
+    }
+
+    /// Merge a world's JSON over the shared defaults and parse it
+    fn parse(
+        world_json: &Value,
+        shared: &Value,
+        packs: &[String],
+    ) -> Result<WorldDefinition, String> {
+        let mut world_json = world_json.clone();
+        json::merge(&mut world_json, shared, false);
+
+        let mut meta: WorldMeta =
+            serde_json::from_value(world_json.clone()).map_err(|error| error.to_string())?;
+        let config: WorldConfig =
+            serde_json::from_value(world_json).map_err(|error| error.to_string())?;
+
+        meta.packs = packs.to_vec();
+
+        Ok((meta, config))
+    }
+
+    /// Names of the texture packs, sorted so the first one, the default, doesn't change between runs
+    pub fn load_packs() -> Vec<String> {
+        let packs = fs::read_dir("./assets/textures/packs/").unwrap();
+        let mut packs = packs
+            .flatten()
+            .map(|e| e.file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        packs.sort();
+        packs
+    }
 }
@@ -7,6 +7,7 @@ use specs::{Builder, Entity as ECSEntity, World, WorldExt};
 use crate::comp::{
     brain::{Brain, BrainOptions},
     curr_chunk::CurrChunk,
+    entity_sync::EntitySync,
     etype::EType,
     rigidbody::RigidBody,
     rotation::Rotation,
@@ -53,6 +54,7 @@ pub struct EntityPrototype {
 pub type EntityPrototypes = HashMap<String, EntityPrototype>;
 
 /// Entities resource stored as a map
+#[derive(Clone)]
 pub struct Entities {
     prototypes: EntityPrototypes,
 }
@@ -65,21 +67,27 @@ impl Default for Entities {
 
 impl Entities {
     pub fn new() -> Self {
-        let entities_json: serde_json::Value =
-            serde_json::from_reader(File::open("assets/metadata/entities.json").unwrap()).unwrap();
+        Self::load().unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Load the prototypes listed in `entities.json`, failing on the first malformed file
+    pub fn load() -> Result<Self, String> {
+        let file = File::open("assets/metadata/entities.json").map_err(|e| e.to_string())?;
+        let entities_json: HashMap<String, String> = serde_json::from_reader(file)
+            .map_err(|error| format!("Malformed entities.json: {}", error))?;
 
         let mut prototypes: EntityPrototypes = HashMap::new();
 
-        for value in entities_json.as_object().unwrap().values() {
-            let value_str = value.as_str().unwrap();
-            let path = format!("./assets/metadata/entities/{}", value_str);
-            let entity: EntityPrototype =
-                serde_json::from_reader(File::open(path).unwrap()).unwrap();
+        for entity_file in entities_json.values() {
+            let path = format!("./assets/metadata/entities/{}", entity_file);
+            let file = File::open(&path).map_err(|e| e.to_string())?;
+            let entity: EntityPrototype = serde_json::from_reader(file)
+                .map_err(|error| format!("Malformed {}: {}", path, error))?;
 
             prototypes.insert(entity.etype.to_owned(), entity);
         }
 
-        Self { prototypes }
+        Ok(Self { prototypes })
     }
 
     /// Clone all prototypes
@@ -127,6 +135,7 @@ impl Entities {
             ))
             .with(Rotation::from_quaternion(&rotation))
             .with(CurrChunk::new())
+            .with(EntitySync::new())
             .with(Target(if observe == "all" {
                 TargetInner::ALL(None)
             } else if observe == "player" {
@@ -1,12 +1,21 @@
 pub mod astar;
+pub mod atlas;
+pub mod chat;
 pub mod chunk;
 pub mod chunks;
 pub mod clock;
+pub mod commands;
 pub mod config;
 pub mod entities;
 pub mod kdtree;
+pub mod palette;
+pub mod permissions;
 pub mod physics;
+pub mod player_data;
 pub mod players;
+pub mod procedural;
 pub mod registry;
 pub mod space;
+pub mod validation;
+pub mod weather;
 pub mod world;
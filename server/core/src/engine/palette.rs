@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    path::Path,
+};
+
+use log::warn;
+use server_utils::convert::parse_chunk_name;
+
+use crate::constants::{MIGRATION_FOLDER, PALETTE_FILE};
+
+use super::{chunk::Chunk, registry::Registry, world::WorldConfig};
+
+/// Block names mapped to the ids a world's chunks were saved with
+pub type Palette = HashMap<String, u32>;
+
+/// Load the palette saved along with a world, `None` if the world predates palettes
+pub fn load(root: &Path) -> Option<Palette> {
+    let file = File::open(root.join(PALETTE_FILE)).ok()?;
+
+    serde_json::from_reader(file)
+        .map_err(|error| warn!("Corrupted palette in {:?}: {}", root, error))
+        .ok()
+}
+
+/// Save the ids of the current registry as the world's palette
+pub fn save(root: &Path, registry: &Registry) -> Result<(), String> {
+    let file = File::create(root.join(PALETTE_FILE)).map_err(|error| error.to_string())?;
+    serde_json::to_writer(file, registry.name_map()).map_err(|error| error.to_string())
+}
+
+/// Map the saved ids whose blocks got renumbered to their current ids, and
+/// the ones whose blocks are gone to `placeholder`
+pub fn id_map(saved: &Palette, registry: &Registry, placeholder: u32) -> HashMap<u32, u32> {
+    saved
+        .iter()
+        .filter_map(|(name, &old_id)| {
+            let new_id = registry.get_id(name).unwrap_or(placeholder);
+            (new_id != old_id).then_some((old_id, new_id))
+        })
+        .collect()
+}
+
+/// Id of the block configured to stand in for missing blocks, air if there's no such block
+pub fn placeholder(config: &WorldConfig, registry: &Registry) -> u32 {
+    registry
+        .get_id(&config.placeholder_block)
+        .unwrap_or_else(|| {
+            warn!(
+                "Placeholder block \"{}\" not found, using air instead.",
+                config.placeholder_block
+            );
+            0
+        })
+}
+
+/// Id a saved block id turns into, given the `id_map` of a world
+pub fn remap(id: u32, map: &HashMap<u32, u32>, registry: &Registry, placeholder: u32) -> u32 {
+    match map.get(&id) {
+        Some(&new_id) => new_id,
+        None if registry.has_type(id) => id,
+        None => placeholder,
+    }
+}
+
+/// Rewrite the saved chunks of a world if block ids changed since it was last saved, then
+/// save the current palette. Returns how many chunks were rewritten.
+///
+/// Worlds without a palette are assumed to use the current ids, only unknown ids are replaced.
+/// Rewritten chunks are staged next to the chunk folder along with the new palette, and only
+/// moved over the old ones once all of them are written, so a migration that stops partway
+/// is either started over or finished on the next load, never applied twice.
+pub fn migrate(
+    root: &Path,
+    chunk_folder: &Path,
+    config: &WorldConfig,
+    registry: &Registry,
+) -> Result<usize, String> {
+    let staging = root.join(MIGRATION_FOLDER);
+
+    if staging.exists() {
+        resume(root, chunk_folder, &staging)?;
+    }
+
+    let saved = load(root);
+
+    if saved.as_ref() == Some(registry.name_map()) {
+        return Ok(0);
+    }
+
+    let placeholder = placeholder(config, registry);
+    let map = saved.map_or_else(HashMap::new, |saved| id_map(&saved, registry, placeholder));
+
+    fs::create_dir_all(&staging).map_err(|error| error.to_string())?;
+
+    let mut count = 0;
+
+    for entry in fs::read_dir(chunk_folder)
+        .map_err(|error| error.to_string())?
+        .flatten()
+    {
+        let file_name = entry.file_name().into_string().unwrap_or_default();
+
+        let chunk_name = match file_name.strip_suffix(".json") {
+            Some(chunk_name) => chunk_name,
+            None => continue,
+        };
+
+        let mut chunk = Chunk::new(parse_chunk_name(chunk_name), config, chunk_folder);
+
+        if chunk.remap_ids(|id| remap(id, &map, registry, placeholder)) {
+            chunk.file = staging.join(&file_name).to_string_lossy().into_owned();
+            chunk.save();
+            count += 1;
+        }
+    }
+
+    // the staged palette marks every chunk as written
+    save(&staging, registry)?;
+    resume(root, chunk_folder, &staging)?;
+
+    Ok(count)
+}
+
+/// Finish a migration whose chunks were all staged, or drop one that stopped before that
+fn resume(root: &Path, chunk_folder: &Path, staging: &Path) -> Result<(), String> {
+    let palette = staging.join(PALETTE_FILE);
+
+    if palette.exists() {
+        for entry in fs::read_dir(staging)
+            .map_err(|error| error.to_string())?
+            .flatten()
+        {
+            let file_name = entry.file_name();
+
+            if file_name != PALETTE_FILE {
+                fs::rename(entry.path(), chunk_folder.join(file_name))
+                    .map_err(|error| error.to_string())?;
+            }
+        }
+
+        fs::rename(&palette, root.join(PALETTE_FILE)).map_err(|error| error.to_string())?;
+    }
+
+    fs::remove_dir_all(staging).map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+    use server_common::types::Block;
+
+    fn block(name: &str) -> Block {
+        serde_json::from_value(json!({
+            "name": name,
+            "rotatable": false,
+            "yRotatable": false,
+            "isBlock": true,
+            "isEmpty": false,
+            "isFluid": false,
+            "isLight": false,
+            "isPlant": false,
+            "isSolid": true,
+            "isTransparent": false,
+            "redLightLevel": 0,
+            "greenLightLevel": 0,
+            "blueLightLevel": 0,
+            "isPlantable": false,
+            "transparentStandalone": false,
+        }))
+        .unwrap()
+    }
+
+    fn registry(blocks: &[(u32, &str)]) -> Registry {
+        Registry::from_blocks(blocks.iter().map(|&(id, name)| (id, block(name))).collect())
+    }
+
+    #[test]
+    fn maps_renumbered_and_removed_blocks() {
+        let saved: Palette = [("Air", 0), ("Stone", 1), ("Dirt", 2), ("Gone", 3)]
+            .iter()
+            .map(|&(name, id)| (name.to_owned(), id))
+            .collect();
+        let registry = registry(&[(0, "Air"), (1, "Stone"), (3, "Dirt"), (4, "Missing")]);
+
+        let map = id_map(&saved, &registry, 4);
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&2], 3);
+        assert_eq!(map[&3], 4);
+    }
+
+    #[test]
+    fn remaps_chained_renumbering_once() {
+        // A moves 3 to 4 while B moves 4 to 5, so A must not end up as B
+        let saved: Palette = [("A", 3), ("B", 4)]
+            .iter()
+            .map(|&(name, id)| (name.to_owned(), id))
+            .collect();
+        let registry = registry(&[(0, "Air"), (4, "A"), (5, "B")]);
+
+        let map = id_map(&saved, &registry, 0);
+
+        assert_eq!(remap(3, &map, &registry, 0), 4);
+        assert_eq!(remap(4, &map, &registry, 0), 5);
+        assert_eq!(remap(0, &map, &registry, 0), 0);
+        assert_eq!(remap(9, &map, &registry, 0), 0);
+    }
+}
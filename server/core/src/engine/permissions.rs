@@ -0,0 +1,268 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::ROLES_FILE,
+    network::{auth::Identity, models::MessageType},
+};
+
+/// What a player is trusted to do in a world, each role includes the ones below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Guest,
+    Builder,
+    Operator,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Role::Guest => "guest",
+            Role::Builder => "builder",
+            Role::Operator => "operator",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "guest" => Ok(Role::Guest),
+            "builder" => Ok(Role::Builder),
+            "operator" => Ok(Role::Operator),
+            _ => Err(format!("Unknown role \"{}\".", s)),
+        }
+    }
+}
+
+/// Actions that are gated behind a role
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    Chat,
+    Move,
+    LoadChunks,
+    Edit,
+    EditSpawn,
+    Configure,
+    Save,
+    Summon,
+    SetSpawn,
+    ManageRoles,
+    Teleport,
+    Mute,
+}
+
+impl Permission {
+    /// Lowest role allowed to perform this action
+    pub fn required_role(&self) -> Role {
+        match self {
+            Permission::Chat | Permission::Move | Permission::LoadChunks => Role::Guest,
+            Permission::Edit => Role::Builder,
+            Permission::EditSpawn
+            | Permission::Configure
+            | Permission::Save
+            | Permission::Summon
+            | Permission::SetSpawn
+            | Permission::ManageRoles
+            | Permission::Teleport
+            | Permission::Mute => Role::Operator,
+        }
+    }
+
+    /// Permission needed to send a message of a type, `None` if clients can't send it at all
+    pub fn for_message(message_type: MessageType) -> Option<Self> {
+        match message_type {
+            MessageType::Request => Some(Permission::LoadChunks),
+            MessageType::Config => Some(Permission::Configure),
+            MessageType::Update => Some(Permission::Edit),
+            MessageType::Peer => Some(Permission::Move),
+            MessageType::Message | MessageType::Complete => Some(Permission::Chat),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self {
+            Permission::Chat => "chat",
+            Permission::Move => "move",
+            Permission::LoadChunks => "load chunks",
+            Permission::Edit => "edit blocks",
+            Permission::EditSpawn => "edit blocks around spawn",
+            Permission::Configure => "change the time or the weather",
+            Permission::Save => "save the world",
+            Permission::Summon => "summon entities",
+            Permission::SetSpawn => "set the spawn",
+            Permission::ManageRoles => "manage roles",
+            Permission::Teleport => "teleport",
+            Permission::Mute => "mute players",
+        };
+
+        write!(f, "{}", action)
+    }
+}
+
+impl Role {
+    pub fn allows(&self, permission: Permission) -> bool {
+        *self >= permission.required_role()
+    }
+}
+
+/// Resource of the roles assigned in a world, keyed by player UUID
+///
+/// Players without an assigned role fall back to `default_role`, or to
+/// `guest_role` if they haven't logged in. Names or UUIDs listed in the
+/// world's `operators` config are always operators.
+pub struct Roles {
+    path: PathBuf,
+    save: bool,
+    default_role: Role,
+    guest_role: Role,
+    operators: Vec<String>,
+    assigned: HashMap<String, Role>,
+}
+
+impl Roles {
+    /// Load the roles previously assigned in a world, from `roles.json` next to its chunks
+    pub fn new(
+        root_folder: &Path,
+        save: bool,
+        default_role: Role,
+        guest_role: Role,
+        operators: Vec<String>,
+    ) -> Self {
+        let mut path = root_folder.to_path_buf();
+        path.push(ROLES_FILE);
+
+        let assigned = if save {
+            File::open(&path)
+                .ok()
+                .and_then(|file| match serde_json::from_reader(file) {
+                    Ok(assigned) => Some(assigned),
+                    Err(error) => {
+                        warn!("Corrupted roles file {:?}: {}", path, error);
+                        None
+                    }
+                })
+                .unwrap_or_default()
+        } else {
+            HashMap::new()
+        };
+
+        Self {
+            path,
+            save,
+            default_role,
+            guest_role,
+            operators,
+            assigned,
+        }
+    }
+
+    /// Role of a player in this world
+    pub fn get(&self, identity: &Identity) -> Role {
+        if let Some(role) = self.assigned.get(&identity.uuid) {
+            return *role;
+        }
+
+        let is_operator = self.operators.iter().any(|operator| {
+            *operator == identity.uuid || (!identity.guest && *operator == identity.name)
+        });
+
+        if is_operator {
+            Role::Operator
+        } else if identity.guest {
+            self.guest_role
+        } else {
+            self.default_role
+        }
+    }
+
+    /// Assign a role to a player, guests can't be assigned roles as their UUIDs don't last
+    pub fn set(&mut self, identity: &Identity, role: Role) -> Result<(), String> {
+        if identity.guest {
+            return Err("Guests can't be given roles, they need to log in first.".to_owned());
+        }
+
+        self.assigned.insert(identity.uuid.to_owned(), role);
+        self.save();
+
+        Ok(())
+    }
+
+    /// Write the assigned roles to `roles.json`
+    pub fn save(&self) {
+        if !self.save {
+            return;
+        }
+
+        let result = File::create(&self.path)
+            .map_err(|error| error.to_string())
+            .and_then(|file| {
+                serde_json::to_writer(file, &self.assigned).map_err(|e| e.to_string())
+            });
+
+        if let Err(error) = result {
+            warn!("Unable to save roles to {:?}: {}", self.path, error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(name: &str, guest: bool) -> Identity {
+        Identity {
+            uuid: format!("uuid-{}", name),
+            name: name.to_owned(),
+            guest,
+        }
+    }
+
+    fn roles() -> Roles {
+        Roles::new(
+            Path::new("."),
+            false,
+            Role::Builder,
+            Role::Guest,
+            vec!["admin".to_owned()],
+        )
+    }
+
+    #[test]
+    fn roles_include_lower_roles() {
+        assert!(Role::Operator.allows(Permission::Edit));
+        assert!(Role::Builder.allows(Permission::Chat));
+        assert!(!Role::Builder.allows(Permission::Save));
+        assert!(!Role::Guest.allows(Permission::Edit));
+    }
+
+    #[test]
+    fn resolves_roles_by_identity() {
+        let mut roles = roles();
+
+        assert_eq!(roles.get(&identity("admin", false)), Role::Operator);
+        assert_eq!(roles.get(&identity("admin", true)), Role::Guest);
+        assert_eq!(roles.get(&identity("steve", false)), Role::Builder);
+
+        roles.set(&identity("steve", false), Role::Guest).unwrap();
+        assert_eq!(roles.get(&identity("steve", false)), Role::Guest);
+
+        assert!(roles.set(&identity("alex", true), Role::Operator).is_err());
+    }
+}
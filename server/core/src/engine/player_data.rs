@@ -0,0 +1,88 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use server_common::vec::Vec3;
+
+use crate::constants::PLAYERS_FOLDER;
+
+/// JSON format of a player's data file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerData {
+    pub name: String,
+    pub position: Vec3<f32>,
+    pub rotation: [f32; 4],
+
+    #[serde(default)]
+    pub state: HashMap<String, serde_json::Value>,
+}
+
+/// Resource to load and save player data files, keyed by player UUID
+pub struct PlayerStore {
+    folder: PathBuf,
+    save: bool,
+}
+
+impl PlayerStore {
+    /// Player files live in a `players` folder next to the world's chunks
+    pub fn new(root_folder: &Path, save: bool) -> Self {
+        let mut folder = root_folder.to_path_buf();
+        folder.push(PLAYERS_FOLDER);
+
+        if save {
+            fs::create_dir_all(&folder).expect("Unable to create players directory...");
+        }
+
+        Self { folder, save }
+    }
+
+    /// Load a player's data, if any has been saved before
+    pub fn load(&self, uuid: &str) -> Option<PlayerData> {
+        if !self.save {
+            return None;
+        }
+
+        let file = File::open(self.path(uuid)).ok()?;
+
+        match serde_json::from_reader(file) {
+            Ok(data) => Some(data),
+            Err(error) => {
+                warn!("Corrupted player data for {}: {}", uuid, error);
+                None
+            }
+        }
+    }
+
+    /// Write a player's data to its file
+    pub fn store(&self, uuid: &str, data: &PlayerData) {
+        if !self.save {
+            return;
+        }
+
+        let result = File::create(self.path(uuid))
+            .map_err(|error| error.to_string())
+            .and_then(|file| serde_json::to_writer(file, data).map_err(|e| e.to_string()));
+
+        if let Err(error) = result {
+            warn!("Unable to save player data for {}: {}", uuid, error);
+        }
+    }
+
+    fn path(&self, uuid: &str) -> PathBuf {
+        // UUIDs only ever contain hex digits and dashes, but never trust a path
+        let file_name = uuid
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+            .collect::<String>();
+
+        let mut path = self.folder.clone();
+        path.push(format!("{}.json", file_name));
+        path
+    }
+}
@@ -6,7 +6,11 @@ use std::{
 use actix::Recipient;
 use specs::Entity;
 
-use super::super::network::{message, models::messages};
+use super::super::network::{
+    auth::Identity,
+    message::{self, LeaveReason},
+    models::{create_chat_message, messages, ChatType, MessageType},
+};
 
 use server_common::vec::Vec2;
 
@@ -19,13 +23,37 @@ pub type PlayerUpdates = HashMap<usize, messages::Peer>;
 pub struct Player {
     pub entity: Entity,
     pub name: Option<String>,
+    pub identity: Identity,
+    // IP address the player connected from, if known
+    pub address: Option<String>,
     pub addr: Recipient<message::Message>,
     pub requested_chunks: VecDeque<Vec2<i32>>,
+    pub state: HashMap<String, serde_json::Value>,
 }
 
 /// Resource to store all server-side players in a HashMap
 pub type Players = HashMap<usize, Player>;
 
+/// Create the LEAVE message announcing that a player has left
+pub fn create_leave_message(
+    player_id: &usize,
+    player: &Player,
+    reason: &LeaveReason,
+) -> messages::Message {
+    let player_name = player.name.as_deref().unwrap_or("Somebody");
+
+    let body = match reason {
+        LeaveReason::Quit => format!("{} left the game", player_name),
+        LeaveReason::TimedOut => format!("{} timed out", player_name),
+        LeaveReason::Transferred(world) => format!("{} went to {}", player_name, world),
+        LeaveReason::Kicked => format!("{} was kicked", player_name),
+    };
+
+    let mut message = create_chat_message(MessageType::Leave, ChatType::Info, "", &body);
+    message.text = player_id.to_string();
+    message
+}
+
 pub trait BroadcastExt {
     fn broadcast(
         &mut self,
@@ -33,20 +61,20 @@ pub trait BroadcastExt {
         include: Vec<usize>,
         exclude: Vec<usize>,
         sender: Option<usize>,
-    ) -> Vec<Player>;
+    ) -> Vec<(usize, Player)>;
 }
 
 impl BroadcastExt for Players {
     /// Broadcast a message to all players. Exclude will be used if include is empty.
     ///
-    /// Returns a list of inactive/disconnected players
+    /// Returns a list of inactive/disconnected players along with their ids
     fn broadcast(
         &mut self,
         msg: &messages::Message,
         include: Vec<usize>,
         exclude: Vec<usize>,
         sender: Option<usize>,
-    ) -> Vec<Player> {
+    ) -> Vec<(usize, Player)> {
         let mut resting_players = vec![];
 
         if let Some(sender) = sender {
@@ -87,7 +115,7 @@ impl BroadcastExt for Players {
 
         resting_players.iter().for_each(|id| {
             if let Some(player) = self.remove(id) {
-                inactives.push(player);
+                inactives.push((*id, player));
             }
         });
 
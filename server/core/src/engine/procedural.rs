@@ -0,0 +1,357 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+};
+
+use image::{imageops, Rgba, RgbaImage};
+use log::warn;
+use serde::Deserialize;
+
+use server_common::noise::Noise;
+
+pub const PROCEDURAL_FOLDER: &str = "assets/textures/procedural";
+
+const CACHE_FOLDER: &str = "assets/textures/generated/procedural";
+
+/// Finds the image file of a pack texture, for procedural textures overlaying one
+pub type FindTexture<'a> = &'a dyn Fn(&str) -> Result<String, String>;
+
+/// JSON format of a procedural texture, its layers applied in field order
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProceduralTexture {
+    #[serde(default = "default_size")]
+    pub size: u32,
+    /// Flat colour, as 0 to 1 RGB or RGBA
+    #[serde(default)]
+    pub color: Option<Vec<f32>>,
+    pub gradient: Option<Gradient>,
+    pub noise: Option<NoiseLayer>,
+    /// Other textures drawn on top, procedural or from the pack
+    #[serde(default)]
+    pub overlays: Vec<String>,
+    pub border: Option<Border>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Gradient {
+    pub from: Vec<f32>,
+    pub to: Vec<f32>,
+    #[serde(default)]
+    pub direction: Direction,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    #[default]
+    Vertical,
+    Horizontal,
+    Diagonal,
+}
+
+/// Brightness variation, `amount` being how far the noise may shift it either way
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoiseLayer {
+    #[serde(default)]
+    pub kind: NoiseKind,
+    #[serde(default = "default_noise_scale")]
+    pub scale: f64,
+    #[serde(default = "default_noise_amount")]
+    pub amount: f64,
+    #[serde(default)]
+    pub seed: u32,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum NoiseKind {
+    #[default]
+    Perlin,
+    Simplex,
+    Ridged,
+    Worley,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Border {
+    pub color: Vec<f32>,
+    #[serde(default = "default_border_width")]
+    pub width: u32,
+}
+
+fn default_size() -> u32 {
+    16
+}
+
+fn default_noise_scale() -> f64 {
+    0.25
+}
+
+fn default_noise_amount() -> f64 {
+    0.2
+}
+
+fn default_border_width() -> u32 {
+    1
+}
+
+/// Read the spec of a procedural texture, checking its colours
+pub fn read(source: &str) -> Result<ProceduralTexture, String> {
+    let path = format!("{}/{}", PROCEDURAL_FOLDER, source);
+    let file =
+        fs::File::open(&path).map_err(|error| format!("Unable to open {}: {}", path, error))?;
+    let spec: ProceduralTexture =
+        serde_json::from_reader(file).map_err(|error| format!("Malformed {}: {}", path, error))?;
+
+    check(&spec).map_err(|reason| format!("Malformed {}: {}", path, reason))?;
+
+    Ok(spec)
+}
+
+/// Check the colours and size of a procedural texture, failing with what's wrong with them
+pub fn check(spec: &ProceduralTexture) -> Result<(), String> {
+    let colors = spec
+        .color
+        .iter()
+        .chain(spec.gradient.iter().flat_map(|g| [&g.from, &g.to]))
+        .chain(spec.border.iter().map(|b| &b.color));
+
+    for color in colors {
+        to_rgba(color)?;
+    }
+
+    if spec.size == 0 {
+        return Err("size can't be 0".to_owned());
+    }
+
+    Ok(())
+}
+
+/// Load a procedural texture, reusing the image cached on disk unless its spec or any
+/// texture it overlays changed since
+pub fn load(source: &str, find: FindTexture) -> Result<RgbaImage, String> {
+    let mut hasher = DefaultHasher::new();
+    hash_inputs(source, find, &mut vec![], &mut hasher)?;
+
+    let stem = source.trim_end_matches(".json");
+    let cached = format!("{}/{}-{:016x}.png", CACHE_FOLDER, stem, hasher.finish());
+
+    if let Ok(image) = image::open(&cached) {
+        return Ok(image.to_rgba8());
+    }
+
+    let image = generate(source, find, &mut vec![])?;
+
+    if let Err(error) = cache(stem, &cached, &image) {
+        warn!("Unable to cache procedural texture {}: {}", source, error);
+    }
+
+    Ok(image)
+}
+
+/// Hash the spec of a texture and everything it overlays, failing on overlay cycles
+fn hash_inputs(
+    source: &str,
+    find: FindTexture,
+    stack: &mut Vec<String>,
+    hasher: &mut DefaultHasher,
+) -> Result<(), String> {
+    if stack.iter().any(|s| s == source) {
+        return Err(format!("Procedural texture {} overlays itself.", source));
+    }
+
+    let path = format!("{}/{}", PROCEDURAL_FOLDER, source);
+    fs::read(&path)
+        .map_err(|error| format!("Unable to open {}: {}", path, error))?
+        .hash(hasher);
+
+    stack.push(source.to_owned());
+
+    for overlay in read(source)?.overlays {
+        if overlay.ends_with(".png") {
+            let path = find(&overlay)?;
+            fs::read(&path)
+                .map_err(|error| format!("Unable to open {}: {}", path, error))?
+                .hash(hasher);
+        } else {
+            hash_inputs(&overlay, find, stack, hasher)?;
+        }
+    }
+
+    stack.pop();
+
+    Ok(())
+}
+
+/// Draw a procedural texture, layer by layer
+fn generate(source: &str, find: FindTexture, stack: &mut Vec<String>) -> Result<RgbaImage, String> {
+    if stack.iter().any(|s| s == source) {
+        return Err(format!("Procedural texture {} overlays itself.", source));
+    }
+
+    let spec = read(source)?;
+    let size = spec.size;
+
+    let base = to_rgba(spec.color.as_deref().unwrap_or(&[1.0, 1.0, 1.0]))?;
+    let mut image = RgbaImage::from_pixel(size, size, base);
+
+    if let Some(Gradient {
+        from,
+        to,
+        direction,
+    }) = &spec.gradient
+    {
+        let (from, to) = (to_rgba(from)?, to_rgba(to)?);
+        let last = (size - 1).max(1) as f32;
+
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let t = match direction {
+                Direction::Vertical => y as f32 / last,
+                Direction::Horizontal => x as f32 / last,
+                Direction::Diagonal => (x + y) as f32 / (last * 2.0),
+            };
+
+            *pixel = mix(from, to, t);
+        }
+    }
+
+    if let Some(layer) = &spec.noise {
+        let noise = Noise::new(layer.seed);
+
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let (nx, ny) = (x as f64 + 0.5, y as f64 + 0.5);
+
+            let value = match layer.kind {
+                NoiseKind::Perlin => noise.perlin2(nx, ny, layer.scale),
+                NoiseKind::Simplex => noise.simplex2(nx, ny, layer.scale),
+                NoiseKind::Ridged => noise.ridged2(nx, ny, layer.scale),
+                NoiseKind::Worley => noise.worley2(nx, ny, layer.scale),
+            };
+
+            let factor = 1.0 + layer.amount * value.clamp(-1.0, 1.0);
+
+            for channel in pixel.0.iter_mut().take(3) {
+                *channel = (*channel as f64 * factor).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+
+    stack.push(source.to_owned());
+
+    for overlay in &spec.overlays {
+        let layer = if overlay.ends_with(".png") {
+            let path = find(overlay)?;
+            image::open(&path)
+                .map_err(|_| format!("Texture not readable: {}", path))?
+                .to_rgba8()
+        } else {
+            generate(overlay, find, stack)?
+        };
+
+        let layer = imageops::resize(&layer, size, size, imageops::FilterType::Nearest);
+        imageops::overlay(&mut image, &layer, 0, 0);
+    }
+
+    stack.pop();
+
+    if let Some(Border { color, width }) = &spec.border {
+        let color = to_rgba(color)?;
+
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let edge = x.min(y).min(size - 1 - x).min(size - 1 - y);
+
+            if edge < *width {
+                *pixel = color;
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+/// Write a generated texture to the cache, dropping the outdated images of the same texture
+fn cache(stem: &str, path: &str, image: &RgbaImage) -> Result<(), String> {
+    fs::create_dir_all(CACHE_FOLDER).map_err(|error| error.to_string())?;
+
+    let prefix = format!("{}-", stem);
+
+    for entry in fs::read_dir(CACHE_FOLDER)
+        .map_err(|error| error.to_string())?
+        .flatten()
+    {
+        let name = entry.file_name().into_string().unwrap_or_default();
+        let hash = name
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(".png"));
+
+        // only this texture's images, not the ones of textures sharing the prefix
+        if hash.is_some_and(|hash| hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+
+    image.save(path).map_err(|error| error.to_string())
+}
+
+/// Convert a 0 to 1 RGB or RGBA colour
+fn to_rgba(color: &[f32]) -> Result<Rgba<u8>, String> {
+    if color.len() != 3 && color.len() != 4 {
+        return Err(format!("colors need 3 or 4 channels, got {}", color.len()));
+    }
+
+    let channel =
+        |i: usize| (color.get(i).copied().unwrap_or(1.0).clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    Ok(Rgba([channel(0), channel(1), channel(2), channel(3)]))
+}
+
+fn mix(from: Rgba<u8>, to: Rgba<u8>, t: f32) -> Rgba<u8> {
+    let mut mixed = from;
+
+    for (channel, (&a, &b)) in mixed.0.iter_mut().zip(from.0.iter().zip(to.0.iter())) {
+        *channel = (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    }
+
+    mixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_colors() {
+        assert_eq!(to_rgba(&[1.0, 0.0, 1.0]), Ok(Rgba([255, 0, 255, 255])));
+        assert_eq!(to_rgba(&[0.0, 0.0, 0.0, 0.5]), Ok(Rgba([0, 0, 0, 128])));
+        assert!(to_rgba(&[1.0, 0.0]).is_err());
+    }
+
+    #[test]
+    fn mixes_colors() {
+        let black = Rgba([0, 0, 0, 255]);
+        let white = Rgba([255, 255, 255, 255]);
+
+        assert_eq!(mix(black, white, 0.0), black);
+        assert_eq!(mix(black, white, 1.0), white);
+        assert_eq!(mix(black, white, 0.5), Rgba([128, 128, 128, 255]));
+    }
+
+    #[test]
+    fn checks_specs_without_their_path() {
+        let spec: ProceduralTexture =
+            serde_json::from_str(r#"{ "size": 0, "color": [1, 0, 0] }"#).unwrap();
+        assert_eq!(check(&spec), Err("size can't be 0".to_owned()));
+
+        let spec: ProceduralTexture = serde_json::from_str(r#"{ "color": [1, 0] }"#).unwrap();
+        assert_eq!(
+            check(&spec),
+            Err("colors need 3 or 4 channels, got 2".to_owned())
+        );
+    }
+}
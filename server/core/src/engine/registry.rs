@@ -2,12 +2,20 @@
 
 use std::collections::HashMap;
 use std::fs::File;
+use std::path::Path;
 
-use serde::{Deserialize, Serialize};
+use image::RgbaImage;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use server_common::types::{Block, TypeMap, UV};
+use server_common::types::{Block, Portal, TypeMap, UV};
 use server_utils::json;
 
+use super::{
+    atlas::{Layout, Shape, MAX_ATLAS_SIZE, TILE_UNITS},
+    procedural,
+    validation::validate_registry,
+};
+
 pub type Ranges = HashMap<String, UV>;
 pub type Blocks = HashMap<u32, Block>;
 
@@ -16,72 +24,210 @@ pub type Blocks = HashMap<u32, Block>;
 #[serde(rename_all = "camelCase")]
 pub struct PackDetails {
     pub dimension: u32,
+    /// Pack to take the textures missing from this one from
+    #[serde(default)]
+    pub base: Option<String>,
+}
+
+/// Where each texture is on the atlas pages of a texture pack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pack {
+    pub ranges: Ranges,
+    pub pages: u32,
+    pub uv_texture_size: u32,
 }
 
+pub type Packs = HashMap<String, Pack>;
+
 /// Resource to control block data and textures
+///
+/// Every pack lays its textures out the same way, so the ranges of the default
+/// pack, used for meshing, hold for any pack a player picks.
 #[derive(Debug, Clone)]
 pub struct Registry {
-    pub atlas: image::RgbaImage,
     pub ranges: Ranges,
     pub blocks: Blocks,
-    pub uv_side_count: u32,
+    pub pages: u32,
     pub uv_texture_size: u32,
+    pub default_pack: String,
+    pub packs: Packs,
 
     name_map: HashMap<String, u32>,
+    /// Where the textures go on the atlas pages, kept to draw the atlases later
+    layout: Layout,
+    sources: Vec<String>,
 }
 
 impl Registry {
-    pub fn new(mut packs: Vec<String>, write: bool) -> Self {
-        if packs.is_empty() {
-            panic!("No texture packs found.");
+    pub fn new(packs: Vec<String>, write: bool) -> Self {
+        let registry = Registry::load(packs).unwrap_or_else(|error| panic!("{}", error));
+
+        if write {
+            registry
+                .write_atlases()
+                .unwrap_or_else(|error| panic!("{}", error));
         }
 
-        let registry: Self = Registry::load_pack(&packs.remove(0), write);
+        registry
+    }
 
-        packs.into_iter().for_each(|name| {
-            Registry::load_pack(&name, write);
-        });
+    /// Load the block definitions and texture packs, failing with every problem found in them
+    pub fn load(packs: Vec<String>) -> Result<Self, String> {
+        let errors = validate_registry(&packs);
 
-        registry
+        if !errors.is_empty() {
+            let errors = errors.iter().map(ToString::to_string).collect::<Vec<_>>();
+            return Err(format!("Invalid registry:\n{}", errors.join("\n")));
+        }
+
+        let default_pack = packs
+            .first()
+            .cloned()
+            .ok_or_else(|| "No texture packs found.".to_owned())?;
+
+        let (blocks, name_map) = Registry::load_blocks()?;
+
+        let mut sources = blocks
+            .values()
+            .flat_map(|block| block.textures.values().cloned())
+            .collect::<Vec<_>>();
+        sources.sort();
+        sources.dedup();
+
+        let animations = blocks
+            .values()
+            .flat_map(|block| block.animations.clone())
+            .collect::<HashMap<_, _>>();
+
+        let mut details = HashMap::new();
+
+        for name in &packs {
+            let pack: PackDetails =
+                read_json(&format!("assets/textures/packs/{}/pack.json", name))?;
+            details.insert(name.to_owned(), pack);
+        }
+
+        // the default pack decides how many tiles each texture spans
+        let default_images = Registry::load_images(&default_pack, &sources)?;
+        let default_dimension = details[&default_pack].dimension;
+
+        let shapes: HashMap<String, Shape> = default_images
+            .iter()
+            .map(|(source, image)| {
+                let frames = animations.get(source).map_or(1, |a| a.frames);
+
+                // procedural textures take a single tile whatever their resolution
+                let shape = if source.ends_with(".png") {
+                    Shape::of(image, default_dimension, frames)
+                } else {
+                    Shape::of(image, image.width(), 1)
+                };
+
+                (source.to_owned(), shape)
+            })
+            .collect();
+
+        let max_dimension = details.values().map(|d| d.dimension).max().unwrap_or(1);
+        let layout = Layout::pack(&shapes, MAX_ATLAS_SIZE * TILE_UNITS / max_dimension)?;
+        let ranges = layout.ranges(&animations);
+
+        let mut loaded = Packs::new();
+
+        for name in packs {
+            let pack = Pack {
+                ranges: ranges.clone(),
+                pages: layout.pages.len() as u32,
+                uv_texture_size: details[&name].dimension,
+            };
+            loaded.insert(name, pack);
+        }
+
+        Ok(Self {
+            ranges,
+            blocks,
+            pages: layout.pages.len() as u32,
+            uv_texture_size: default_dimension,
+            default_pack,
+            packs: loaded,
+            name_map,
+            layout,
+            sources,
+        })
     }
 
-    /// Load a texture pack
-    pub fn load_pack(pack_name: &str, write: bool) -> Self {
-        let blocks_json: HashMap<String, String> =
-            serde_json::from_reader(File::open("assets/metadata/blocks.json").unwrap()).unwrap();
+    /// Draw the atlas pages of every texture pack for clients to fetch
+    pub fn write_atlases(&self) -> Result<(), String> {
+        for (name, pack) in &self.packs {
+            let images = Registry::load_images(name, &self.sources)?;
+
+            for (page, atlas) in self
+                .layout
+                .draw(&images, pack.uv_texture_size)
+                .iter()
+                .enumerate()
+            {
+                atlas
+                    .save(&format!(
+                        "assets/textures/generated/{}-atlas-{}.png",
+                        name, page
+                    ))
+                    .map_err(|error| error.to_string())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Registry of the given blocks only, without any textures
+    #[cfg(test)]
+    pub fn from_blocks(blocks: Blocks) -> Self {
+        let name_map = blocks
+            .iter()
+            .map(|(id, block)| (block.name.clone(), *id))
+            .collect();
+
+        Self {
+            ranges: HashMap::new(),
+            blocks,
+            pages: 0,
+            uv_texture_size: 0,
+            default_pack: String::new(),
+            packs: HashMap::new(),
+            name_map,
+            layout: Layout {
+                textures: HashMap::new(),
+                pages: vec![],
+            },
+            sources: vec![],
+        }
+    }
+
+    /// Load the block definitions listed in `blocks.json`
+    fn load_blocks() -> Result<(Blocks, HashMap<String, u32>), String> {
+        let blocks_json: HashMap<String, String> = read_json("assets/metadata/blocks.json")?;
 
         let mut base_cache: HashMap<String, serde_json::Value> = HashMap::new();
-        let mut texture_map: HashMap<String, image::DynamicImage> = HashMap::new();
 
         let mut name_map = HashMap::new();
 
         let mut blocks: Blocks = HashMap::new();
 
-        let pack: PackDetails = serde_json::from_reader(
-            File::open(format!("assets/textures/packs/{}/pack.json", pack_name)).unwrap(),
-        )
-        .unwrap();
-
         for (id, block_file) in blocks_json.iter() {
-            let path = format!("./assets/metadata/blocks/{}", block_file);
             let mut block_json: serde_json::Value =
-                serde_json::from_reader(File::open(path).unwrap()).unwrap();
+                read_json(&format!("./assets/metadata/blocks/{}", block_file))?;
 
-            let base = &block_json["base"];
-
-            let base = match base {
+            let base = match &block_json["base"] {
                 serde_json::Value::String(base_str) => {
-                    Some(base_cache.entry(base_str.to_owned()).or_insert_with(|| {
-                        serde_json::from_reader(
-                            File::open(format!("./assets/metadata/blocks/{}", base_str).as_str())
-                                .unwrap(),
-                        )
-                        .unwrap()
-                    }))
+                    if !base_cache.contains_key(base_str) {
+                        let base = read_json(&format!("./assets/metadata/blocks/{}", base_str))?;
+                        base_cache.insert(base_str.to_owned(), base);
+                    }
+
+                    &base_cache[base_str]
                 }
-                _ => None,
-            }
-            .unwrap();
+                _ => return Err(format!("Block {} has no base.", block_file)),
+            };
 
             json::merge(&mut block_json, base, false);
 
@@ -89,131 +235,107 @@ impl Registry {
             let mut textures_hash = HashMap::new();
 
             if !serde_json::Value::is_null(textures) {
-                for (side, img_src) in textures.as_object().unwrap().iter() {
-                    let img_src_str = img_src.as_str().unwrap();
-
-                    let image = if img_src_str.ends_with(".png") {
-                        let path =
-                            format!("assets/textures/packs/{}/blocks/{}", pack_name, img_src_str);
-                        image::open(&path).unwrap_or_else(|_| panic!("Texture not found: {}", path))
-                    } else {
-                        // texture data
-                        let texture_data: serde_json::Value = serde_json::from_reader(
-                            File::open(format!("assets/textures/procedural/{}", img_src_str))
-                                .unwrap(),
-                        )
-                        .unwrap();
-
-                        let color_vec = texture_data["color"].as_array().unwrap().as_slice();
-
-                        let color_r = (color_vec[0].as_f64().unwrap() * 255.0) as u8;
-                        let color_g = (color_vec[1].as_f64().unwrap() * 255.0) as u8;
-                        let color_b = (color_vec[2].as_f64().unwrap() * 255.0) as u8;
-
-                        let imgbuf = image::ImageBuffer::from_pixel(
-                            16,
-                            16,
-                            image::Rgb([color_r, color_g, color_b]),
-                        );
-
-                        image::DynamicImage::ImageRgb8(imgbuf)
-                    };
-
-                    texture_map.insert(img_src_str.to_owned(), image);
+                let textures = textures
+                    .as_object()
+                    .ok_or_else(|| format!("Malformed textures in block {}", block_file))?;
+
+                for (side, img_src) in textures.iter() {
+                    let img_src_str = img_src
+                        .as_str()
+                        .ok_or_else(|| format!("Malformed textures in block {}", block_file))?;
+
                     textures_hash.insert(side.to_owned(), img_src_str.to_owned());
                 }
             }
 
-            let mut new_block: Block = serde_json::from_value(block_json).unwrap();
+            let mut new_block: Block = serde_json::from_value(block_json)
+                .map_err(|error| format!("Malformed block {}: {}", block_file, error))?;
             new_block.textures = textures_hash;
-            let id = id.parse::<u32>().unwrap();
+            let id = id
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid block id: {}", id))?;
             name_map.insert(new_block.name.clone(), id);
             blocks.insert(id, new_block);
         }
 
-        // OBTAINED TEXTURE MAP
-        let map_size = texture_map.len() as f32;
-        let mut shifts = 1;
-        let count_per_side = map_size.sqrt().ceil() as u32;
-        while 1 << shifts < count_per_side {
-            shifts += 1;
-        }
-        let count_per_side = 1 << shifts;
-        let texture_dim = pack.dimension;
-        let atlas_width = count_per_side * texture_dim;
-        let atlas_height = count_per_side * texture_dim;
-
-        let mut atlas: image::RgbaImage = image::ImageBuffer::new(atlas_width, atlas_height);
-
-        let mut ranges = HashMap::new();
-
-        let mut row = 0;
-        let mut col = 0;
-
-        let mut texture_map_vec: Vec<_> = texture_map.into_iter().collect();
-        texture_map_vec.sort_by(|x, y| x.0.cmp(&y.0));
-
-        for (key, image) in texture_map_vec {
-            if col >= count_per_side {
-                col = 0;
-                row += 1;
-            }
-
-            let start_x = col * texture_dim;
-            let start_y = row * texture_dim;
-
-            let resized = image::imageops::resize(
-                &image,
-                texture_dim,
-                texture_dim,
-                image::imageops::FilterType::CatmullRom,
-            );
-
-            image::imageops::overlay(&mut atlas, &resized, start_x, start_y);
+        Ok((blocks, name_map))
+    }
 
-            let f_start_x = start_x as f32;
-            let f_start_y = start_y as f32;
+    /// Load the images of `sources` from a texture pack
+    fn load_images(
+        pack_name: &str,
+        sources: &[String],
+    ) -> Result<HashMap<String, RgbaImage>, String> {
+        sources
+            .iter()
+            .map(|source| {
+                Registry::load_texture(pack_name, source)
+                    .map(|image| (source.to_owned(), image.to_rgba8()))
+            })
+            .collect()
+    }
 
-            let f_atlas_width = atlas_width as f32;
-            let f_atlas_height = atlas_height as f32;
+    /// Load a texture of a pack, generating procedural ones
+    fn load_texture(pack_name: &str, source: &str) -> Result<image::DynamicImage, String> {
+        if !source.ends_with(".png") {
+            let find = |overlay: &str| Registry::find_texture(pack_name, overlay);
+            return procedural::load(source, &find).map(image::DynamicImage::ImageRgba8);
+        }
 
-            let start_u = f_start_x / f_atlas_width;
-            let end_u = (f_start_x + texture_dim as f32) / f_atlas_width;
-            let start_v = 1.0 - f_start_y / f_atlas_height;
-            let end_v = 1.0 - (f_start_y + texture_dim as f32) / f_atlas_height;
+        let path = Registry::find_texture(pack_name, source)?;
+        image::open(&path).map_err(|_| format!("Texture not readable: {}", path))
+    }
 
-            let (start_u, start_v, end_u, end_v) =
-                fix_texture_bleeding((start_u, start_v, end_u, end_v));
+    /// Path of a texture of a pack, following its `base` packs for the images it doesn't have
+    fn find_texture(pack_name: &str, source: &str) -> Result<String, String> {
+        let mut visited: Vec<String> = vec![];
+        let mut current = pack_name.to_owned();
 
-            let uv = UV {
-                start_u,
-                end_u,
-                start_v,
-                end_v,
-            };
+        loop {
+            let path = format!("assets/textures/packs/{}/blocks/{}", current, source);
 
-            ranges.insert(key, uv);
+            if Path::new(&path).exists() {
+                return Ok(path);
+            }
 
-            col += 1;
+            let details: PackDetails =
+                read_json(&format!("assets/textures/packs/{}/pack.json", current))?;
+            visited.push(current);
+
+            match details.base {
+                Some(base) if !visited.contains(&base) => current = base,
+                _ => {
+                    return Err(format!(
+                        "Texture not found: {} in pack {}",
+                        source, pack_name
+                    ))
+                }
+            }
         }
+    }
 
-        if write {
-            atlas
-                .save(&format!(
-                    "assets/textures/generated/{}-atlas.png",
-                    pack_name
-                ))
-                .unwrap();
+    /// Check that every block of this registry keeps its id and name in `other`,
+    /// so chunks saved with this registry still read the same under `other`
+    pub fn check_stable(&self, other: &Registry) -> Result<(), String> {
+        let mut ids = self.blocks.keys().collect::<Vec<_>>();
+        ids.sort();
+
+        for id in ids {
+            let name = &self.blocks[id].name;
+
+            match other.blocks.get(id) {
+                Some(block) if &block.name == name => {}
+                Some(block) => {
+                    return Err(format!(
+                        "Block id {} changed from {} to {}.",
+                        id, name, block.name
+                    ))
+                }
+                None => return Err(format!("Block {} (id {}) was removed.", name, id)),
+            }
         }
 
-        Self {
-            atlas,
-            ranges,
-            blocks,
-            uv_texture_size: texture_dim,
-            uv_side_count: count_per_side,
-            name_map,
-        }
+        Ok(())
     }
 
     /// Get block transparency by id
@@ -352,19 +474,47 @@ impl Registry {
         type_map
     }
 
-    /// Get solids that can be treated as empty's
+    /// Get solids that can be treated as empty's, portals are walked into
     pub fn get_passable_solids(&self) -> Vec<u32> {
         self.blocks
             .iter()
-            .filter(|&(_, b)| !b.is_solid && (b.is_block || b.is_plant))
+            .filter(|&(_, b)| (!b.is_solid && (b.is_block || b.is_plant)) || b.portal.is_some())
             .map(|(id, _)| *id)
             .collect()
     }
 
+    /// Destination of a portal block, `None` for every other block
+    pub fn get_portal(&self, id: u32) -> Option<&Portal> {
+        self.get_block_by_id(id).portal.as_ref()
+    }
+
     /// Check if registery contains type
     pub fn has_type(&self, id: u32) -> bool {
         self.blocks.contains_key(&id)
     }
+
+    /// Get block id by name, `None` if there's no such block
+    pub fn get_id(&self, name: &str) -> Option<u32> {
+        self.name_map.get(name).copied()
+    }
+
+    /// Every block name with its id
+    pub fn name_map(&self) -> &HashMap<String, u32> {
+        &self.name_map
+    }
+
+    /// Find a block id by its name, ignoring case
+    pub fn find_id_by_name(&self, name: &str) -> Option<u32> {
+        self.name_map
+            .iter()
+            .find(|(block_name, _)| block_name.eq_ignore_ascii_case(name))
+            .map(|(_, &id)| id)
+    }
+
+    /// Names of all registered blocks
+    pub fn block_names(&self) -> Vec<&str> {
+        self.name_map.keys().map(|name| name.as_str()).collect()
+    }
 }
 
 /// Get the JSON string of texture type
@@ -382,16 +532,8 @@ pub fn get_texture_type(texture: &HashMap<String, String>) -> &str {
     }
 }
 
-/// Fixing texture bleeding with the
-/// [Half-texel edge correction method](http://drilian.com/2008/11/25/understanding-half-pixel-and-half-texel-offsets/)
-fn fix_texture_bleeding(
-    (start_u, start_v, end_u, end_v): (f32, f32, f32, f32),
-) -> (f32, f32, f32, f32) {
-    let offset = 0.1 / 128 as f32;
-    (
-        start_u + offset,
-        start_v - offset,
-        end_u - offset,
-        end_v + offset,
-    )
+/// Read and parse a JSON definition file
+fn read_json<T: DeserializeOwned>(path: &str) -> Result<T, String> {
+    let file = File::open(path).map_err(|error| format!("Unable to open {}: {}", path, error))?;
+    serde_json::from_reader(file).map_err(|error| format!("Malformed {}: {}", path, error))
 }
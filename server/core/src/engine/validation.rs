@@ -0,0 +1,509 @@
+use std::{collections::HashMap, fmt, fs::File, path::Path};
+
+use serde::{
+    de::{DeserializeOwned, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_json::Value;
+
+use server_common::types::{Animation, Block};
+use server_utils::json;
+
+use super::{
+    atlas::TILE_UNITS,
+    procedural::{self, ProceduralTexture, PROCEDURAL_FOLDER},
+    registry::PackDetails,
+};
+
+const BLOCKS_FILE: &str = "assets/metadata/blocks.json";
+const BLOCKS_FOLDER: &str = "assets/metadata/blocks";
+const PACKS_FOLDER: &str = "assets/textures/packs";
+
+/// Highest level a light channel can hold
+pub const MAX_LIGHT_LEVEL: u32 = 15;
+
+/// A problem in the block or texture pack metadata
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistryError {
+    /// A file couldn't be opened
+    Unreadable { path: String, reason: String },
+    /// A file isn't valid JSON, or doesn't have the expected fields
+    Malformed { path: String, reason: String },
+    /// A key of `blocks.json` isn't a number
+    InvalidId { id: String },
+    /// The same id is listed more than once in `blocks.json`
+    DuplicateId { id: u32, files: Vec<String> },
+    /// Different blocks share a name
+    DuplicateName { name: String, ids: Vec<u32> },
+    /// A block doesn't say which base it extends
+    MissingBase { block: String },
+    /// A block extends a base file that can't be read
+    BadBase {
+        block: String,
+        base: String,
+        reason: String,
+    },
+    /// A procedural texture of a block doesn't exist
+    MissingProcedural { block: String, texture: String },
+    /// A procedural texture overlays itself, directly or through other textures
+    OverlayCycle { texture: String },
+    /// Neither a pack nor any of its bases has a texture of a block
+    MissingTexture {
+        block: String,
+        pack: String,
+        texture: String,
+    },
+    /// A pack inherits from a pack that doesn't exist, or from itself
+    BadPackBase { pack: String, base: String },
+    /// A light level of a block doesn't fit in a light channel
+    InvalidLightLevel {
+        block: String,
+        channel: &'static str,
+        level: u32,
+    },
+    /// A light filter of a block lets through less than none or more than all of a channel
+    InvalidLightFilter { block: String, filter: [f32; 3] },
+    /// A pack's tiles can't be split into atlas units
+    InvalidDimension { pack: String, dimension: u32 },
+    /// A block animates a texture it doesn't have, or has no frames or frame time
+    InvalidAnimation { block: String, texture: String },
+    /// Blocks animate the same texture differently
+    ConflictingAnimation {
+        texture: String,
+        blocks: Vec<String>,
+    },
+    /// There's no texture pack at all
+    NoPacks,
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unreadable { path, reason } => write!(f, "Unable to open {}: {}", path, reason),
+            Self::Malformed { path, reason } => write!(f, "Malformed {}: {}", path, reason),
+            Self::InvalidId { id } => write!(f, "Invalid block id \"{}\" in blocks.json", id),
+            Self::DuplicateId { id, files } => {
+                write!(f, "Block id {} is used by {}", id, files.join(", "))
+            }
+            Self::DuplicateName { name, ids } => {
+                let ids = ids.iter().map(u32::to_string).collect::<Vec<_>>();
+                write!(f, "Block name {} is used by ids {}", name, ids.join(", "))
+            }
+            Self::MissingBase { block } => write!(f, "Block {} has no base", block),
+            Self::BadBase {
+                block,
+                base,
+                reason,
+            } => write!(
+                f,
+                "Base {} of block {} is unusable: {}",
+                base, block, reason
+            ),
+            Self::MissingProcedural { block, texture } => write!(
+                f,
+                "Procedural texture {} of block {} not found",
+                texture, block
+            ),
+            Self::OverlayCycle { texture } => {
+                write!(f, "Procedural texture {} overlays itself", texture)
+            }
+            Self::MissingTexture {
+                block,
+                pack,
+                texture,
+            } => write!(
+                f,
+                "Texture {} of block {} not found in pack {}",
+                texture, block, pack
+            ),
+            Self::BadPackBase { pack, base } => {
+                write!(f, "Pack {} has an invalid base pack {}", pack, base)
+            }
+            Self::InvalidLightLevel {
+                block,
+                channel,
+                level,
+            } => write!(
+                f,
+                "Block {} has a {} light level of {}, the maximum is {}",
+                block, channel, level, MAX_LIGHT_LEVEL
+            ),
+            Self::InvalidLightFilter { block, filter } => write!(
+                f,
+                "Block {} has a light filter of {:?}, each channel must be from 0 to 1",
+                block, filter
+            ),
+            Self::InvalidDimension { pack, dimension } => write!(
+                f,
+                "Pack {} has a dimension of {}, it must be a multiple of {}",
+                pack, dimension, TILE_UNITS
+            ),
+            Self::InvalidAnimation { block, texture } => write!(
+                f,
+                "Animation of {} in block {} needs a texture of the block, frames and a frame time",
+                texture, block
+            ),
+            Self::ConflictingAnimation { texture, blocks } => write!(
+                f,
+                "Texture {} is animated differently by {}",
+                texture,
+                blocks.join(", ")
+            ),
+            Self::NoPacks => write!(f, "No texture packs found"),
+        }
+    }
+}
+
+/// Entries of a JSON object in file order, keeping repeated keys
+struct Entries(Vec<(String, String)>);
+
+impl<'de> Deserialize<'de> for Entries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = Entries;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a map of block ids to block files")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Entries, A::Error> {
+                let mut entries = vec![];
+
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+
+                Ok(Entries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+/// Check the block definitions and the texture packs `packs`, reporting every
+/// problem found instead of stopping at the first one
+pub fn validate_registry(packs: &[String]) -> Vec<RegistryError> {
+    let mut errors = vec![];
+
+    let Entries(entries) = match read(BLOCKS_FILE) {
+        Ok(entries) => entries,
+        Err(error) => return vec![error],
+    };
+
+    let mut files_by_id: HashMap<u32, Vec<String>> = HashMap::new();
+
+    for (id, file) in entries {
+        match id.parse::<u32>() {
+            Ok(id) => files_by_id.entry(id).or_default().push(file),
+            Err(_) => errors.push(RegistryError::InvalidId { id }),
+        }
+    }
+
+    let mut ids = files_by_id.keys().copied().collect::<Vec<_>>();
+    ids.sort_unstable();
+
+    let mut ids_by_name: HashMap<String, Vec<u32>> = HashMap::new();
+    let mut textures: Vec<(String, String)> = vec![];
+    let mut animations: HashMap<String, Vec<(String, Animation)>> = HashMap::new();
+
+    for id in ids {
+        let files = &files_by_id[&id];
+
+        if files.len() > 1 {
+            errors.push(RegistryError::DuplicateId {
+                id,
+                files: files.clone(),
+            });
+        }
+
+        for file in files {
+            let block = match load_block(file) {
+                Ok(block) => block,
+                Err(error) => {
+                    errors.push(error);
+                    continue;
+                }
+            };
+
+            for (channel, level) in [
+                ("red", block.red_light_level),
+                ("green", block.green_light_level),
+                ("blue", block.blue_light_level),
+            ] {
+                if level > MAX_LIGHT_LEVEL {
+                    errors.push(RegistryError::InvalidLightLevel {
+                        block: block.name.clone(),
+                        channel,
+                        level,
+                    });
+                }
+            }
+
+            if let Some(filter) = block.light_filter {
+                if filter.iter().any(|share| !(0.0..=1.0).contains(share)) {
+                    errors.push(RegistryError::InvalidLightFilter {
+                        block: block.name.clone(),
+                        filter,
+                    });
+                }
+            }
+
+            let mut sources = block.textures.values().cloned().collect::<Vec<_>>();
+            sources.sort();
+            sources.dedup();
+
+            let mut animated = block.animations.iter().collect::<Vec<_>>();
+            animated.sort_by_key(|&(texture, _)| texture);
+
+            for (texture, animation) in animated {
+                if !sources.contains(texture)
+                    || animation.frames == 0
+                    || animation.frame_time <= 0.0
+                {
+                    errors.push(RegistryError::InvalidAnimation {
+                        block: block.name.clone(),
+                        texture: texture.to_owned(),
+                    });
+                }
+
+                animations
+                    .entry(texture.to_owned())
+                    .or_default()
+                    .push((block.name.clone(), animation.clone()));
+            }
+
+            for source in sources {
+                textures.push((block.name.clone(), source));
+            }
+
+            ids_by_name.entry(block.name).or_default().push(id);
+        }
+    }
+
+    let mut names = ids_by_name.into_iter().collect::<Vec<_>>();
+    names.sort();
+
+    for (name, mut ids) in names {
+        ids.dedup();
+
+        if ids.len() > 1 {
+            errors.push(RegistryError::DuplicateName { name, ids });
+        }
+    }
+
+    let mut animations = animations.into_iter().collect::<Vec<_>>();
+    animations.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    for (texture, declared) in animations {
+        if declared
+            .iter()
+            .any(|(_, animation)| animation != &declared[0].1)
+        {
+            errors.push(RegistryError::ConflictingAnimation {
+                texture,
+                blocks: declared.into_iter().map(|(block, _)| block).collect(),
+            });
+        }
+    }
+
+    let mut checked = vec![];
+    let mut overlays = vec![];
+
+    for (block, texture) in &textures {
+        if !texture.ends_with(".png") {
+            check_procedural(
+                block,
+                texture,
+                &mut vec![],
+                &mut checked,
+                &mut overlays,
+                &mut errors,
+            );
+        }
+    }
+
+    // images overlaid by procedural textures have to be in the packs like block textures
+    textures.extend(overlays);
+
+    if packs.is_empty() {
+        errors.push(RegistryError::NoPacks);
+    }
+
+    for pack in packs {
+        let chain = match pack_chain(pack, packs) {
+            Ok(chain) => chain,
+            Err(error) => {
+                errors.push(error);
+                continue;
+            }
+        };
+
+        for (block, texture) in textures
+            .iter()
+            .filter(|(_, texture)| texture.ends_with(".png"))
+        {
+            let found = chain.iter().any(|name| {
+                Path::new(PACKS_FOLDER)
+                    .join(name)
+                    .join("blocks")
+                    .join(texture)
+                    .exists()
+            });
+
+            if !found {
+                errors.push(RegistryError::MissingTexture {
+                    block: block.to_owned(),
+                    pack: pack.to_owned(),
+                    texture: texture.to_owned(),
+                });
+            }
+        }
+    }
+
+    errors
+}
+
+/// Check a procedural texture of `block` and the procedural textures it overlays,
+/// collecting the images it overlays to look up in the packs
+fn check_procedural(
+    block: &str,
+    texture: &str,
+    stack: &mut Vec<String>,
+    checked: &mut Vec<String>,
+    overlays: &mut Vec<(String, String)>,
+    errors: &mut Vec<RegistryError>,
+) {
+    if stack.iter().any(|visited| visited == texture) {
+        errors.push(RegistryError::OverlayCycle {
+            texture: texture.to_owned(),
+        });
+        return;
+    }
+
+    if checked.iter().any(|done| done == texture) {
+        return;
+    }
+
+    checked.push(texture.to_owned());
+
+    if !Path::new(PROCEDURAL_FOLDER).join(texture).exists() {
+        errors.push(RegistryError::MissingProcedural {
+            block: block.to_owned(),
+            texture: texture.to_owned(),
+        });
+        return;
+    }
+
+    let path = format!("{}/{}", PROCEDURAL_FOLDER, texture);
+
+    let spec: ProceduralTexture = match read(&path) {
+        Ok(spec) => spec,
+        Err(error) => {
+            errors.push(error);
+            return;
+        }
+    };
+
+    if let Err(reason) = procedural::check(&spec) {
+        errors.push(RegistryError::Malformed { path, reason });
+    }
+
+    stack.push(texture.to_owned());
+
+    for overlay in spec.overlays {
+        if overlay.ends_with(".png") {
+            overlays.push((block.to_owned(), overlay));
+        } else {
+            check_procedural(block, &overlay, stack, checked, overlays, errors);
+        }
+    }
+
+    stack.pop();
+}
+
+/// Load a block file merged with its base
+fn load_block(file: &str) -> Result<Block, RegistryError> {
+    let path = format!("{}/{}", BLOCKS_FOLDER, file);
+    let mut block_json: Value = read(&path)?;
+
+    let base = match &block_json["base"] {
+        Value::String(base) => base.to_owned(),
+        _ => {
+            return Err(RegistryError::MissingBase {
+                block: file.to_owned(),
+            })
+        }
+    };
+
+    let base_json: Value =
+        read(&format!("{}/{}", BLOCKS_FOLDER, base)).map_err(|error| RegistryError::BadBase {
+            block: file.to_owned(),
+            base,
+            reason: error.to_string(),
+        })?;
+
+    json::merge(&mut block_json, &base_json, false);
+
+    serde_json::from_value(block_json).map_err(|error| RegistryError::Malformed {
+        path,
+        reason: error.to_string(),
+    })
+}
+
+/// A pack followed by the packs it inherits textures from, in lookup order
+fn pack_chain(pack: &str, packs: &[String]) -> Result<Vec<String>, RegistryError> {
+    let mut chain = vec![pack.to_owned()];
+
+    loop {
+        let current = chain.last().unwrap();
+        let details: PackDetails = read(&format!("{}/{}/pack.json", PACKS_FOLDER, current))?;
+
+        if details.dimension == 0 || !details.dimension.is_multiple_of(TILE_UNITS) {
+            return Err(RegistryError::InvalidDimension {
+                pack: current.to_owned(),
+                dimension: details.dimension,
+            });
+        }
+
+        match details.base {
+            None => return Ok(chain),
+            Some(base) if chain.contains(&base) || !packs.contains(&base) => {
+                return Err(RegistryError::BadPackBase {
+                    pack: current.to_owned(),
+                    base,
+                })
+            }
+            Some(base) => chain.push(base),
+        }
+    }
+}
+
+fn read<T: DeserializeOwned>(path: &str) -> Result<T, RegistryError> {
+    let file = File::open(path).map_err(|error| RegistryError::Unreadable {
+        path: path.to_owned(),
+        reason: error.to_string(),
+    })?;
+
+    serde_json::from_reader(file).map_err(|error| RegistryError::Malformed {
+        path: path.to_owned(),
+        reason: error.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_keep_repeated_keys() {
+        let Entries(entries) =
+            serde_json::from_str(r#"{ "1": "dirt.json", "2": "sand.json", "1": "stone.json" }"#)
+                .unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[2], ("1".to_owned(), "stone.json".to_owned()));
+    }
+}
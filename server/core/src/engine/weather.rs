@@ -0,0 +1,52 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// Resource of the current weather of a world
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Weather {
+    #[default]
+    Clear,
+    Rain,
+    Storm,
+}
+
+impl Weather {
+    /// How much of the sunlight gets through, multiplied into the clients' skylight
+    pub fn skylight(&self) -> f32 {
+        match self {
+            Weather::Clear => 1.0,
+            Weather::Rain => 0.7,
+            Weather::Storm => 0.4,
+        }
+    }
+}
+
+impl fmt::Display for Weather {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Weather::Clear => "clear",
+            Weather::Rain => "rain",
+            Weather::Storm => "storm",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Weather {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "clear" => Ok(Weather::Clear),
+            "rain" => Ok(Weather::Rain),
+            "storm" => Ok(Weather::Storm),
+            _ => Err(format!(
+                "Unknown weather \"{}\", try clear, rain or storm.",
+                s
+            )),
+        }
+    }
+}
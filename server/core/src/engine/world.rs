@@ -7,8 +7,10 @@ use ansi_term::Colour::Yellow;
 
 use specs::shred::{Fetch, FetchMut, Resource};
 
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::{collections::VecDeque, fs::File};
 
 use specs::{Builder, DispatcherBuilder, World as ECSWorld, WorldExt};
@@ -19,6 +21,7 @@ use server_common::quaternion::Quaternion;
 
 use crate::comp::brain::Brain;
 use crate::comp::curr_chunk::CurrChunk;
+use crate::comp::entity_sync::EntitySync;
 use crate::comp::etype::EType;
 use crate::comp::id::Id;
 use crate::comp::name::Name;
@@ -26,25 +29,40 @@ use crate::comp::rotation::Rotation;
 use crate::comp::target::Target;
 use crate::comp::view_radius::ViewRadius;
 use crate::comp::walk_towards::WalkTowards;
-use crate::network::models::{create_of_type, ChatType};
+use crate::gen::biomes::Biomes;
+use crate::network::models::{
+    create_config_message, create_despawn_message, create_error_message, create_kick_message,
+    create_registry_message, create_teleport_message, create_texture_pack_message,
+    create_weather_message, ChatType,
+};
 use crate::sys::{
     BroadcastSystem, ChunkingSystem, EntitiesSystem, GenerationSystem, MeshingSystem,
-    ObserveSystem, PathFindSystem, PeersSystem, SearchSystem, WalkTowardsSystem,
+    ObserveSystem, PathFindSystem, PeersSystem, PortalsSystem, SearchSystem, WalkTowardsSystem,
 };
 use crate::{
     comp::rigidbody::RigidBody,
-    network::message::{JoinResult, Message},
+    network::{
+        auth::Identity,
+        message::{JoinData, LeaveReason, Message, PlayerSummary},
+    },
 };
 
+use super::chat::{
+    Channel, ChatHistory, ChatLimiter, ChatOutbox, OutgoingChat, SharedMutes, CHANNEL_STATE,
+    IGNORED_STATE, TEAM_STATE,
+};
+use super::commands::{builtin_commands, Command, Commands, ParseContext};
 use super::entities::Entities;
 use super::kdtree::KdTree;
+use super::permissions::{Permission, Role, Roles};
+use super::player_data::{PlayerData, PlayerStore};
 use super::{
     super::{
-        constants::WORLD_DATA_FILE,
+        constants::{SPAWN_SEARCH_RADIUS, WORLD_DATA_FILE},
         engine::chunks::MeshLevel,
         network::models::{
-            create_chat_message, create_message, messages, ChunkProtocol, MessageComponents,
-            MessageType,
+            create_chat_message, create_message, create_of_type, messages, ChunkProtocol,
+            MessageComponents, MessageType,
         },
         sys::PhysicsSystem,
     },
@@ -52,21 +70,28 @@ use super::{
     players::Player,
 };
 
-use server_common::{
-    aabb::Aabb,
-    vec::{Vec2, Vec3},
-};
+use server_common::{aabb::Aabb, vec::Vec3};
 
 use super::chunks::Chunks;
 use super::clock::Clock;
-use super::players::{BroadcastExt, PlayerUpdates, Players};
+use super::players::{create_leave_message, BroadcastExt, PlayerUpdates, Players};
 use super::registry::Registry;
+use super::weather::Weather;
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct WorldData {
     time: f32,
     tick_speed: f32,
+
+    #[serde(default)]
+    spawn: Option<Vec3<i32>>,
+
+    #[serde(default)]
+    weather: Weather,
+
+    #[serde(default)]
+    time_locked: bool,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -86,6 +111,106 @@ pub struct WorldConfig {
     pub player_head: f32,
     pub max_per_thread: usize,
     pub server_tick_rate: u64,
+    #[serde(default = "default_ping_interval")]
+    pub ping_interval: u64,
+    #[serde(default = "default_ping_timeout")]
+    pub ping_timeout: u64,
+    #[serde(default = "default_max_players")]
+    pub max_players: usize,
+    #[serde(default = "default_max_queue_size")]
+    pub max_queue_size: usize,
+    #[serde(default = "default_entity_update_rate")]
+    pub entity_update_rate: u64,
+    #[serde(default = "default_entity_keyframe_interval")]
+    pub entity_keyframe_interval: u64,
+    #[serde(default = "default_entity_position_precision")]
+    pub entity_position_precision: f32,
+    #[serde(default = "default_entity_direction_precision")]
+    pub entity_direction_precision: f32,
+    #[serde(default = "default_spawn_protection")]
+    pub spawn_protection: i32,
+    #[serde(default)]
+    pub operators: Vec<String>,
+    #[serde(default = "default_role")]
+    pub default_role: Role,
+    #[serde(default = "default_role")]
+    pub guest_role: Role,
+    #[serde(default = "default_chat_history_size")]
+    pub chat_history_size: usize,
+    #[serde(default = "default_chat_rate_limit")]
+    pub chat_rate_limit: usize,
+    #[serde(default = "default_chat_rate_window")]
+    pub chat_rate_window: u64,
+    /// Seconds a saved world stays loaded without players, 0 to keep it loaded
+    #[serde(default = "default_unload_after")]
+    pub unload_after: u64,
+    /// Block that saved blocks missing from the registry turn into
+    #[serde(default = "default_placeholder_block")]
+    pub placeholder_block: String,
+    /// Let the server mix sunlight and colored light per vertex, by day and by night
+    #[serde(default)]
+    pub mix_lights: bool,
+}
+
+// defaults of the configs added since the first `worlds.json`, matching the shipped values
+fn default_ping_interval() -> u64 {
+    50000
+}
+
+fn default_ping_timeout() -> u64 {
+    150000
+}
+
+fn default_max_players() -> usize {
+    10
+}
+
+fn default_max_queue_size() -> usize {
+    10
+}
+
+fn default_entity_update_rate() -> u64 {
+    50
+}
+
+fn default_entity_keyframe_interval() -> u64 {
+    2000
+}
+
+fn default_entity_position_precision() -> f32 {
+    0.01
+}
+
+fn default_entity_direction_precision() -> f32 {
+    0.05
+}
+
+fn default_spawn_protection() -> i32 {
+    16
+}
+
+fn default_role() -> Role {
+    Role::Builder
+}
+
+fn default_chat_history_size() -> usize {
+    50
+}
+
+fn default_chat_rate_limit() -> usize {
+    5
+}
+
+fn default_chat_rate_window() -> u64 {
+    5000
+}
+
+fn default_unload_after() -> u64 {
+    300
+}
+
+fn default_placeholder_block() -> String {
+    "Missing".to_owned()
 }
 
 #[derive(Deserialize, Clone)]
@@ -100,6 +225,14 @@ pub struct WorldMeta {
     #[serde(default)]
     pub packs: Vec<String>,
 }
+/// Player state key of the texture pack a player picked
+pub const TEXTURE_PACK_STATE: &str = "texturePack";
+
+/// Resource of the players standing in a portal, who need to step out of it before
+/// a portal takes them anywhere again
+#[derive(Default)]
+pub struct InPortals(pub HashSet<usize>);
+
 /// A single voxel-based and ECS-based world
 pub struct World {
     pub ecs: ECSWorld,
@@ -117,11 +250,61 @@ pub type MessagesQueue = Vec<(
     Option<usize>,      // by who
 )>;
 
+/// Resource to throttle entity updates to the configured network rate
+pub struct EntityUpdateTimer {
+    last_flush: Instant,
+}
+
+impl EntityUpdateTimer {
+    pub fn new() -> Self {
+        Self {
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Returns true and resets the timer if at least `rate` milliseconds
+    /// have passed since the last flush.
+    pub fn ready(&mut self, rate: u64) -> bool {
+        if self.last_flush.elapsed().as_millis() as u64 >= rate {
+            self.last_flush = Instant::now();
+            return true;
+        }
+
+        false
+    }
+}
+
+impl Default for EntityUpdateTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Resource of the world spawn point, `None` until set with `/setspawn`
+#[derive(Default)]
+pub struct Spawn(pub Option<Vec3<i32>>);
+
+/// Position of a body standing on top of a voxel, at the center of the voxel
+pub fn voxel_to_standing(voxel: &Vec3<i32>, dimension: f32) -> Vec3<f32> {
+    let Vec3(vx, vy, vz) = voxel;
+
+    Vec3(
+        (*vx as f32 + 0.5) * dimension,
+        (*vy + 1) as f32 * dimension,
+        (*vz as f32 + 0.5) * dimension,
+    )
+}
+
 impl World {
     /// Instantiate a new voxel world, registers the necessary components and resources
     ///
     /// Attempts to save the world data to its corresponding JSON file.
-    pub fn new(meta: WorldMeta, config: WorldConfig, registry: Registry) -> Self {
+    pub fn new(
+        meta: WorldMeta,
+        config: WorldConfig,
+        registry: Registry,
+        mutes: SharedMutes,
+    ) -> Self {
         let WorldMeta {
             name,
             description,
@@ -136,6 +319,7 @@ impl World {
         // ECS Components
         ecs.register::<Brain>();
         ecs.register::<CurrChunk>();
+        ecs.register::<EntitySync>();
         ecs.register::<EType>();
         ecs.register::<Id>();
         ecs.register::<Target>();
@@ -147,12 +331,35 @@ impl World {
 
         // ECS Resources
         ecs.insert(name.to_owned());
-        ecs.insert(Chunks::new(&name, config.clone(), registry));
+        let chunks = Chunks::new(&name, config.clone(), registry);
+        ecs.insert(PlayerStore::new(&chunks.root_folder, config.save));
+        ecs.insert(Roles::new(
+            &chunks.root_folder,
+            config.save,
+            config.default_role,
+            config.guest_role,
+            config.operators.clone(),
+        ));
+        ecs.insert(chunks);
         ecs.insert(Clock::new(time, tick_speed));
+        ecs.insert(Weather::default());
         ecs.insert(KdTree::new());
         ecs.insert(Players::new());
         ecs.insert(PlayerUpdates::new());
         ecs.insert(MessagesQueue::new());
+        ecs.insert(EntityUpdateTimer::new());
+        ecs.insert(Spawn::default());
+        ecs.insert(InPortals::default());
+        ecs.insert(ChatHistory::new(config.chat_history_size));
+        ecs.insert(ChatLimiter::default());
+        ecs.insert(ChatOutbox::new());
+        ecs.insert(mutes);
+
+        let mut commands = Commands::default();
+        builtin_commands()
+            .into_iter()
+            .for_each(|command| commands.register(command));
+        ecs.insert(commands);
         ecs.insert(Entities::new());
         ecs.insert(Physics::new(PhysicsOptions {
             gravity: Vec3(0.0, -24.0, 0.0),
@@ -220,21 +427,88 @@ impl World {
         );
     }
 
+    /// Whether the world has reached its `max_players` cap
+    pub fn is_full(&self) -> bool {
+        self.read_resource::<Players>().len() >= self.read_resource::<WorldConfig>().max_players
+    }
+
+    /// Where new players spawn, as the voxel they stand on
+    ///
+    /// The configured spawn is used as is when safe, otherwise the closest safe column
+    /// around it (or around `0,0`) is searched for.
+    pub fn spawn_point(&self) -> Vec3<i32> {
+        let chunks = self.read_resource::<Chunks>();
+
+        match self.read_resource::<Spawn>().0.to_owned() {
+            Some(Vec3(vx, vy, vz)) if chunks.is_safe_spawn(vx, vy, vz) => Vec3(vx, vy, vz),
+            Some(Vec3(vx, _, vz)) => chunks.find_safe_spawn(vx, vz, SPAWN_SEARCH_RADIUS),
+            None => chunks.find_safe_spawn(0, 0, SPAWN_SEARCH_RADIUS),
+        }
+    }
+
+    /// Role of a player in this world, `None` if the player isn't in this world
+    pub fn role(&self, player_id: usize) -> Option<Role> {
+        let players = self.read_resource::<Players>();
+        let player = players.get(&player_id)?;
+
+        Some(self.read_resource::<Roles>().get(&player.identity))
+    }
+
+    /// Whether a player's role allows an action
+    pub fn has_permission(&self, player_id: usize, permission: Permission) -> bool {
+        self.role(player_id)
+            .is_some_and(|role| role.allows(permission))
+    }
+
+    /// Assign a role to an online player, persisted with the world
+    pub fn set_role(&mut self, player_id: usize, role: Role) -> Result<(), String> {
+        let identity = self
+            .read_resource::<Players>()
+            .get(&player_id)
+            .map(|player| player.identity.to_owned())
+            .ok_or_else(|| "Player is not in this world.".to_owned())?;
+
+        self.write_resource::<Roles>().set(&identity, role)
+    }
+
+    /// Whether a voxel is within the spawn protection radius
+    pub fn is_spawn_protected(&self, vx: i32, vz: i32) -> bool {
+        let radius = self.read_resource::<WorldConfig>().spawn_protection;
+
+        if radius <= 0 {
+            return false;
+        }
+
+        let center = match self.read_resource::<Spawn>().0.to_owned() {
+            Some(spawn) => spawn,
+            None => Vec3(0, 0, 0),
+        };
+
+        (vx - center.0).abs() <= radius && (vz - center.2).abs() <= radius
+    }
+
     /// Add a new player, signaled from the server
     pub fn add_player(
         &mut self,
         id: Option<usize>,
         player_name: Option<String>,
+        identity: Identity,
+        address: Option<String>,
         player_addr: Recipient<Message>,
-    ) -> JoinResult {
+        position: Option<[i32; 3]>,
+    ) -> JoinData {
         let mut id = id.unwrap_or_else(rand::random::<usize>);
 
+        let Vec3(sx, sy, sz) = self.spawn_point();
+
         let clock = self.read_resource::<Clock>();
         let chunks = self.read_resource::<Chunks>();
 
         let time = clock.time;
-        let tick_speed = clock.tick_speed;
-        let spawn = [0, chunks.get_max_height(0, 0) as i32, 0];
+        let tick_speed = clock.effective_tick_speed();
+        let weather = *self.read_resource::<Weather>();
+        let history = self.read_resource::<ChatHistory>().to_vec();
+        let mut spawn = [sx, sy, sz];
         let passables = chunks.registry.get_passable_solids();
 
         drop(clock);
@@ -256,27 +530,58 @@ impl World {
         let dimension = config.player_dimensions.clone();
         let render_radius = config.render_radius as i16;
         let head = config.player_head;
+        let ping_interval = config.ping_interval;
+        let ping_timeout = config.ping_timeout;
+        let dimension_scale = config.dimension as f32;
 
         drop(config);
 
+        let mut body = RigidBody::new(
+            Aabb::new(
+                &Vec3(spawn[0] as f32, spawn[1] as f32, spawn[2] as f32),
+                &dimension,
+            ),
+            head,
+            1.0,
+            1.0,
+            0.0,
+            0.0,
+            false,
+        );
+        let mut rotation = [0.0, 0.0, 0.0, 0.0];
+        let mut state = HashMap::new();
+
+        let saved = if identity.guest {
+            None
+        } else {
+            self.read_resource::<PlayerStore>().load(&identity.uuid)
+        };
+
+        // returning players pick up where they left off
+        if let Some(data) = saved {
+            let Vec3(px, py, pz) = data.position;
+
+            body.set_position(&Vec3(px, py, pz));
+            spawn = [px.floor() as i32, py.floor() as i32 - 1, pz.floor() as i32];
+            rotation = data.rotation;
+            state = data.state;
+        }
+
+        // teleported in from another world
+        if let Some([vx, vy, vz]) = position {
+            body.set_position(&voxel_to_standing(&Vec3(vx, vy, vz), dimension_scale));
+            spawn = [vx, vy, vz];
+        }
+
+        let [qx, qy, qz, qw] = rotation;
+
         let entity = self
             .ecs_mut()
             .create_entity()
             .with(Id::new(id.to_owned()))
             .with(Name::new(&player_name))
-            .with(RigidBody::new(
-                Aabb::new(
-                    &Vec3(spawn[0] as f32, spawn[1] as f32, spawn[2] as f32),
-                    &dimension,
-                ),
-                head,
-                1.0,
-                1.0,
-                0.0,
-                0.0,
-                false,
-            ))
-            .with(Rotation::new(0.0, 0.0, 0.0, 0.0))
+            .with(body)
+            .with(Rotation::new(qx, qy, qz, qw))
             .with(CurrChunk::new())
             .with(ViewRadius::new(render_radius))
             .build();
@@ -286,59 +591,111 @@ impl World {
         let new_player = Player {
             entity,
             name: player_name,
+            identity,
+            address,
             addr: player_addr,
             requested_chunks: VecDeque::default(),
+            state,
         };
 
         players.insert(id, new_player);
+        drop(players);
+
+        // players arriving inside a portal have to step out of it first
+        self.write_resource::<InPortals>().0.insert(id);
+
+        let texture_pack = self.texture_pack(id);
 
-        JoinResult {
+        JoinData {
             id,
             time,
             tick_speed,
             spawn,
+            rotation,
+            weather,
+            history,
             passables,
+            texture_pack,
+            ping_interval,
+            ping_timeout,
         }
     }
 
     /// Remove a player, signaled from the server
-    pub fn remove_player(&mut self, player_id: &usize) {
+    pub fn remove_player(&mut self, player_id: &usize, reason: &LeaveReason) {
         let name = self.name.to_owned();
         let mut players = self.write_resource::<Players>();
-        let mut message_queue = Vec::new();
 
-        let player = players.remove(player_id);
-
-        if player.is_none() {
-            return;
-        }
+        let player = match players.remove(player_id) {
+            Some(player) => player,
+            None => return,
+        };
 
-        let player = player.unwrap();
         drop(players);
 
-        let player_name = player.name.unwrap_or_else(|| "Somebody".to_owned());
+        self.write_resource::<ChatLimiter>().forget(*player_id);
+        self.write_resource::<InPortals>().0.remove(player_id);
+        self.store_player(&player);
 
         self.ecs_mut()
             .delete_entity(player.entity)
             .expect("Error removing player entity...");
 
-        let mut new_message = create_chat_message(
-            MessageType::Leave,
-            ChatType::Info,
-            "",
-            format!("{} left the game", player_name).as_str(),
-        );
-        new_message.text = player_id.to_string();
+        let new_message = create_leave_message(player_id, &player, reason);
 
-        let message = format!("{} left the world {}", player_name, name);
+        let player_name = player.name.unwrap_or_else(|| "Somebody".to_owned());
+        let message = format!("{} left the world {} ({:?})", player_name, name, reason);
 
         info!("{}", Yellow.bold().paint(message));
 
-        message_queue.push(new_message);
+        self.broadcast(&new_message, vec![], vec![]);
+    }
 
-        message_queue.into_iter().for_each(|message| {
-            self.broadcast(&message, vec![], vec![]);
-        })
+    /// Snapshot of a player's position, rotation and custom state
+    pub fn player_data(&self, player: &Player) -> PlayerData {
+        let bodies = self.ecs().read_component::<RigidBody>();
+        let rotations = self.ecs().read_component::<Rotation>();
+
+        let position = bodies
+            .get(player.entity)
+            .map(|body| body.get_position())
+            .unwrap_or_default();
+        let rotation = rotations
+            .get(player.entity)
+            .map(|r| [r.0 .0, r.0 .1, r.0 .2, r.0 .3])
+            .unwrap_or_default();
+
+        PlayerData {
+            name: player.name.to_owned().unwrap_or_default(),
+            position,
+            rotation,
+            state: player.state.to_owned(),
+        }
+    }
+
+    /// Save a player's data file, guests aren't persisted
+    fn store_player(&self, player: &Player) {
+        if player.identity.guest {
+            return;
+        }
+
+        let data = self.player_data(player);
+        self.read_resource::<PlayerStore>()
+            .store(&player.identity.uuid, &data);
+    }
+
+    /// Get a custom state value of a player
+    pub fn get_player_state(&self, player_id: usize, key: &str) -> Option<serde_json::Value> {
+        self.read_resource::<Players>()
+            .get(&player_id)
+            .and_then(|player| player.state.get(key).cloned())
+    }
+
+    /// Set a custom state value of a player, persisted with the player's data
+    pub fn set_player_state(&mut self, player_id: usize, key: &str, value: serde_json::Value) {
+        if let Some(player) = self.write_resource::<Players>().get_mut(&player_id) {
+            player.state.insert(key.to_owned(), value);
+        }
     }
 
     /// Broadcast a message instantly
@@ -367,56 +724,176 @@ impl World {
         ))
     }
 
+    /// Send an ERROR message to a single player instantly
+    pub fn send_error(&mut self, player_id: usize, reason: &str) {
+        self.broadcast(&create_error_message(reason), vec![player_id], vec![]);
+    }
+
     /// Handles server-side chunk request
     pub fn on_chunk_request(&mut self, player_id: usize, msg: messages::Message) {
-        let mut players = self.write_resource::<Players>();
-
-        let json = msg.parse_json().unwrap();
+        let coords = match msg.chunk_request() {
+            Ok(coords) => coords,
+            Err(reason) => {
+                self.send_error(player_id, &reason);
+                return;
+            }
+        };
 
-        let cx = json["x"].as_i64().unwrap() as i32;
-        let cz = json["z"].as_i64().unwrap() as i32;
+        let mut players = self.write_resource::<Players>();
 
         if let Some(player) = players.get_mut(&player_id) {
-            player.requested_chunks.push_back(Vec2(cx, cz));
+            player.requested_chunks.push_back(coords);
         }
     }
 
     /// Handles server-side config change
     pub fn on_config(&mut self, player_id: usize, msg: messages::Message) {
-        let mut clock = self.write_resource::<Clock>();
-
-        let json = msg.parse_json().unwrap();
+        let (time, tick_speed) = match msg.config_values() {
+            Ok(values) => values,
+            Err(reason) => {
+                self.send_error(player_id, &reason);
+                return;
+            }
+        };
 
-        let time = json["time"].as_f64();
-        let tick_speed = json["tickSpeed"].as_f64();
+        let mut clock = self.write_resource::<Clock>();
 
         if let Some(time) = time {
-            clock.time = time as f32;
+            clock.set_time(time);
         }
 
         if let Some(tick_speed) = tick_speed {
-            clock.tick_speed = tick_speed as f32;
+            clock.set_tick_speed(tick_speed);
         }
 
-        // damn?
         drop(clock);
 
-        let mut new_message = create_of_type(MessageType::Config);
-        new_message.json = json.to_string();
+        self.broadcast_clock();
+    }
+
+    /// Send the clock to every client, with the tick speed they should run at
+    pub fn broadcast_clock(&mut self) {
+        let clock = self.read_resource::<Clock>();
+        let new_message =
+            create_config_message(Some(clock.time), Some(clock.effective_tick_speed()));
+
+        drop(clock);
+
+        self.broadcast(&new_message, vec![], vec![]);
+    }
+
+    /// Set the time of day, wrapped into a single day
+    pub fn set_time(&mut self, time: f32) {
+        self.write_resource::<Clock>().set_time(time);
+        self.broadcast_clock();
+    }
+
+    /// Set how fast time passes
+    pub fn set_tick_speed(&mut self, tick_speed: f32) {
+        self.write_resource::<Clock>().set_tick_speed(tick_speed);
+        self.broadcast_clock();
+    }
+
+    /// Stop or resume the day/night cycle
+    pub fn set_time_locked(&mut self, locked: bool) {
+        self.write_resource::<Clock>().locked = locked;
+        self.broadcast_clock();
+    }
+
+    /// Texture pack a player picked, or the default one if it's gone or never picked
+    pub fn texture_pack(&self, player_id: usize) -> String {
+        let players = self.read_resource::<Players>();
+        let chunks = self.read_resource::<Chunks>();
+
+        players
+            .get(&player_id)
+            .and_then(|player| player.state.get(TEXTURE_PACK_STATE))
+            .and_then(|pack| pack.as_str())
+            .filter(|pack| chunks.registry.packs.contains_key(*pack))
+            .unwrap_or(chunks.registry.default_pack.as_str())
+            .to_owned()
+    }
+
+    /// Switch a player to another texture pack, remembered with its data
+    pub fn set_texture_pack(&mut self, player_id: usize, pack: &str) -> Result<(), String> {
+        let name = {
+            let chunks = self.read_resource::<Chunks>();
+            let mut names = chunks.registry.packs.keys().collect::<Vec<_>>();
+            names.sort();
+
+            names
+                .into_iter()
+                .find(|name| name.eq_ignore_ascii_case(pack))
+                .cloned()
+                .ok_or_else(|| format!("Unknown texture pack \"{}\".", pack))?
+        };
+
+        self.set_player_state(player_id, TEXTURE_PACK_STATE, name.to_owned().into());
+        self.broadcast(&create_texture_pack_message(&name), vec![player_id], vec![]);
 
-        self.broadcast_lazy(&new_message, vec![], vec![], player_id);
+        Ok(())
+    }
+
+    /// Names of the texture packs players can pick from
+    pub fn texture_packs(&self) -> Vec<String> {
+        let mut names = self
+            .read_resource::<Chunks>()
+            .registry
+            .packs
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Swap in reloaded definitions, then have every player fetch them and reload their chunks
+    pub fn reload_definitions(
+        &mut self,
+        registry: Registry,
+        biomes: Arc<Biomes>,
+        entities: Entities,
+    ) {
+        let passables = registry.get_passable_solids();
+
+        self.write_resource::<Chunks>().reload(registry, biomes);
+        self.ecs.insert(entities);
+
+        self.broadcast(&create_registry_message(passables), vec![], vec![]);
+    }
+
+    /// Change the weather, which dims the skylight of every client
+    pub fn set_weather(&mut self, weather: Weather) {
+        *self.write_resource::<Weather>() = weather;
+
+        let new_message = create_weather_message(&weather.to_string(), weather.skylight());
+        self.broadcast(&new_message, vec![], vec![]);
     }
 
     /// Handles server-side voxel updates
     ///
     /// Remesh chunks based on which sub-chunks are changed according to internal
-    /// chunk caching system.
-    pub fn on_update(&mut self, _player_id: usize, msg: messages::Message) {
+    /// chunk caching system. Edits near spawn are dropped unless the player may edit spawn.
+    pub fn on_update(&mut self, player_id: usize, msg: messages::Message) {
+        let mut updates = msg.updates;
+
+        if !self.has_permission(player_id, Permission::EditSpawn) {
+            let count = updates.len();
+            updates.retain(|update| !self.is_spawn_protected(update.vx, update.vz));
+
+            if updates.len() < count {
+                self.send_error(player_id, "Blocks around spawn are protected.");
+            }
+
+            if updates.is_empty() {
+                return;
+            }
+        }
+
         let mut chunks = self.write_resource::<Chunks>();
 
         let &air = chunks.registry.get_id_by_name("Air");
 
-        let mut updates = msg.updates;
         let mut results = vec![];
 
         while !updates.is_empty() {
@@ -433,9 +910,9 @@ impl World {
                 continue;
             }
 
-            let chunk = chunks.get_chunk_by_voxel(vx, vy, vz).unwrap();
-            if chunk.needs_propagation {
-                continue;
+            match chunks.get_chunk_by_voxel(vx, vy, vz) {
+                Some(chunk) if !chunk.needs_propagation => {}
+                _ => continue,
             }
 
             let current_id = chunks.get_voxel_by_voxel(vx, vy, vz);
@@ -479,11 +956,15 @@ impl World {
         cache.iter().for_each(|coords| {
             let mut chunks = self.write_resource::<Chunks>();
 
-            let levels = chunks.raw(&coords).unwrap().dirty_levels.clone();
+            let levels = match chunks.raw(coords) {
+                Some(chunk) => chunk.dirty_levels.clone(),
+                None => return,
+            };
             let mesh_level = MeshLevel::Levels(levels);
 
-            let chunk = chunks.get(&coords, &mesh_level, true).unwrap();
-            chunk_mesh_protocols.push(chunk.get_protocol(true, false, false, mesh_level));
+            if let Some(chunk) = chunks.get(coords, &mesh_level, true) {
+                chunk_mesh_protocols.push(chunk.get_protocol(true, false, false, mesh_level));
+            }
 
             drop(chunks);
         });
@@ -494,11 +975,10 @@ impl World {
         let mut components = MessageComponents::default_for(MessageType::Update);
         let mut chunk_protocols: Vec<ChunkProtocol> = cache
             .iter()
-            .map(|coords| {
+            .filter_map(|coords| {
                 chunks
                     .get_chunk(coords)
-                    .unwrap()
-                    .get_protocol(false, false, true, MeshLevel::None)
+                    .map(|chunk| chunk.get_protocol(false, false, true, MeshLevel::None))
             })
             .collect();
         chunk_protocols.append(&mut chunk_mesh_protocols);
@@ -513,68 +993,446 @@ impl World {
 
     /// Adds the player update to the resource `PlayerUpdate`, handled later in an ECS system.
     pub fn on_peer(&mut self, player_id: usize, msg: messages::Message) {
+        let peer = match msg.peers.into_iter().next() {
+            Some(peer) => peer,
+            None => {
+                self.send_error(player_id, "Peer message has no peer data.");
+                return;
+            }
+        };
+
         let mut player_updates = self.write_resource::<PlayerUpdates>();
-        player_updates.insert(player_id, msg.peers[0].clone());
+        player_updates.insert(player_id, peer);
     }
 
-    /// Handles an incoming chat message, broadcasts response lazily
-    pub fn on_chat_message(&mut self, player_id: usize, msg: messages::Message) {
-        if let Some(message) = msg.message.clone() {
-            let sender: String = message.sender;
-            let body: String = message.body;
+    /// Handles an incoming chat message, sending it to the sender's channel
+    pub fn on_chat_message(&mut self, player_id: usize, mut msg: messages::Message) {
+        // never trust the sender name the client claims
+        let (sender, uuid) = match self.read_resource::<Players>().get(&player_id) {
+            Some(player) => (
+                player.name.to_owned().unwrap_or_default(),
+                player.identity.uuid.to_owned(),
+            ),
+            None => return,
+        };
 
-            info!("{}: {}", sender, body);
+        let body = match msg.message.as_mut() {
+            Some(message) => {
+                message.sender = sender.to_owned();
+                message.body.to_owned()
+            }
+            None => return,
+        };
 
-            if body.starts_with('/') {
-                let body = body
-                    .strip_prefix('/')
-                    .unwrap()
-                    .split_whitespace()
-                    .collect::<Vec<_>>();
+        info!("{}: {}", sender, body);
 
-                let mut msgs = vec![];
+        let config = self.read_resource::<WorldConfig>();
+        let limit = config.chat_rate_limit;
+        let window = Duration::from_millis(config.chat_rate_window);
+        drop(config);
 
-                let create_msg = |chat_type: ChatType, body: &str| {
-                    create_chat_message(MessageType::Message, chat_type, "", body)
-                };
+        if !self
+            .write_resource::<ChatLimiter>()
+            .allow(player_id, limit, window)
+        {
+            let reason = "You are sending messages too fast, slow down.";
+            return self.send_chat(player_id, ChatType::Error, reason);
+        }
 
-                if body.is_empty() {
-                    msgs.push(create_msg(ChatType::Error, "Unknown command."));
-                } else {
-                    match body[0] {
-                        "save" => {
-                            self.save();
-                            msgs.push(create_msg(ChatType::Info, "World has been saved."));
-                        }
-                        "summon" => {
-                            self.test_entity(player_id);
-                            msgs.push(create_msg(ChatType::Info, "Summoned a test entity."));
-                        }
-                        _ => {}
+        if let Some(command) = body.strip_prefix('/') {
+            return self.run_command(player_id, command);
+        }
+
+        if self.is_muted(player_id) {
+            return self.send_chat(player_id, ChatType::Error, "You are muted.");
+        }
+
+        match self.channel(player_id) {
+            Channel::World => {
+                self.write_resource::<ChatHistory>().push(msg.to_owned());
+
+                let recipients = self.chat_recipients(&uuid, None);
+                if !recipients.is_empty() {
+                    self.broadcast_lazy(&msg, recipients, vec![], player_id);
+                }
+            }
+            Channel::Team => {
+                let team = match self.team(player_id) {
+                    Some(team) => team,
+                    None => {
+                        let reason = "You are not in a team, join one with /team <name>.";
+                        return self.send_chat(player_id, ChatType::Error, reason);
                     }
+                };
+
+                if let Some(message) = msg.message.as_mut() {
+                    message.sender = format!("[{}] {}", team, sender);
                 }
 
-                msgs.into_iter().for_each(|msg| {
-                    self.broadcast_lazy(&msg, vec![], vec![], player_id);
-                });
-            } else {
-                self.broadcast_lazy(&msg, vec![], vec![], player_id);
+                self.write_resource::<ChatOutbox>()
+                    .push(OutgoingChat::Broadcast {
+                        team: Some(team),
+                        sender_uuid: uuid,
+                        message: msg,
+                    });
+            }
+            Channel::Global => {
+                if let Some(message) = msg.message.as_mut() {
+                    message.sender = format!("[global] {}", sender);
+                }
+
+                self.write_resource::<ChatOutbox>()
+                    .push(OutgoingChat::Broadcast {
+                        team: None,
+                        sender_uuid: uuid,
+                        message: msg,
+                    });
             }
         }
     }
 
-    /// TEST:
-    ///
-    /// Used to test entity spawning
-    pub fn test_entity(&mut self, player_id: usize) {
+    /// Send a private message to a player by name, in any world
+    pub fn send_private(&mut self, player_id: usize, to: &str, body: &str) -> Result<(), String> {
+        if self.is_muted(player_id) {
+            return Err("You are muted.".to_owned());
+        }
+
+        let (sender, uuid) = match self.read_resource::<Players>().get(&player_id) {
+            Some(player) => (
+                player.name.to_owned().unwrap_or_default(),
+                player.identity.uuid.to_owned(),
+            ),
+            None => return Err("Player is not in this world.".to_owned()),
+        };
+
+        if sender.eq_ignore_ascii_case(to) {
+            return Err("You can't message yourself.".to_owned());
+        }
+
+        let message = create_chat_message(
+            MessageType::Message,
+            ChatType::Player,
+            &format!("{} (private)", sender),
+            body,
+        );
+
+        self.write_resource::<ChatOutbox>()
+            .push(OutgoingChat::Private {
+                sender_id: player_id,
+                sender_uuid: uuid,
+                to: to.to_owned(),
+                message,
+            });
+
+        Ok(())
+    }
+
+    /// Hand a team or global chat message to the players of this world that want it
+    pub fn deliver_chat(
+        &mut self,
+        team: Option<&str>,
+        sender_uuid: &str,
+        message: &messages::Message,
+    ) {
+        if team.is_none() {
+            self.write_resource::<ChatHistory>()
+                .push(message.to_owned());
+        }
+
+        let recipients = self.chat_recipients(sender_uuid, team);
+        if !recipients.is_empty() {
+            self.broadcast(message, recipients, vec![]);
+        }
+    }
+
+    /// Hand a private message to a player of this world, false if nobody here has that name
+    pub fn deliver_private(
+        &mut self,
+        to: &str,
+        sender_uuid: &str,
+        message: &messages::Message,
+    ) -> bool {
+        let recipient = self
+            .read_resource::<Players>()
+            .iter()
+            .find(|(_, player)| {
+                player
+                    .name
+                    .as_ref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(to))
+            })
+            .map(|(id, _)| *id);
+
+        let recipient = match recipient {
+            Some(recipient) => recipient,
+            None => return false,
+        };
+
+        // ignored senders aren't told, the message is just dropped
+        if !self.ignores(recipient, sender_uuid) {
+            self.broadcast(message, vec![recipient], vec![]);
+        }
+
+        true
+    }
+
+    /// Players of this world that should receive a message from `sender_uuid`, optionally
+    /// restricted to a team
+    fn chat_recipients(&self, sender_uuid: &str, team: Option<&str>) -> Vec<usize> {
         let players = self.read_resource::<Players>();
-        let player = players.get(&player_id);
 
-        if player.is_none() {
-            return;
+        players
+            .keys()
+            .filter(|id| !self.ignores(**id, sender_uuid))
+            .filter(|id| team.is_none() || self.team(**id).as_deref() == team)
+            .cloned()
+            .collect()
+    }
+
+    /// Channel a player's chat messages go to
+    pub fn channel(&self, player_id: usize) -> Channel {
+        self.get_player_state(player_id, CHANNEL_STATE)
+            .and_then(|value| value.as_str().and_then(|name| name.parse().ok()))
+            .unwrap_or_default()
+    }
+
+    pub fn set_channel(&mut self, player_id: usize, channel: Channel) {
+        self.set_player_state(player_id, CHANNEL_STATE, channel.to_string().into());
+    }
+
+    /// Team a player is in, if any
+    pub fn team(&self, player_id: usize) -> Option<String> {
+        self.get_player_state(player_id, TEAM_STATE)
+            .and_then(|value| value.as_str().map(|team| team.to_owned()))
+    }
+
+    /// Join a team, or leave it with `None`
+    pub fn set_team(&mut self, player_id: usize, team: Option<&str>) {
+        let value = team.map_or(serde_json::Value::Null, |team| team.to_lowercase().into());
+        self.set_player_state(player_id, TEAM_STATE, value);
+    }
+
+    /// UUID and address a player is muted by
+    fn mute_keys(&self, player_id: usize) -> Vec<String> {
+        self.read_resource::<Players>()
+            .get(&player_id)
+            .map(|player| {
+                std::iter::once(player.identity.uuid.to_owned())
+                    .chain(player.address.to_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether a player has been muted and is still serving it
+    pub fn is_muted(&self, player_id: usize) -> bool {
+        let keys = self.mute_keys(player_id);
+        let keys = keys.iter().map(String::as_str).collect::<Vec<_>>();
+
+        self.read_resource::<SharedMutes>()
+            .read()
+            .unwrap()
+            .is_muted(&keys)
+    }
+
+    /// Stop a player from chatting in every world, for `duration` or until unmuted
+    pub fn mute_player(&mut self, player_id: usize, duration: Option<Duration>) {
+        let keys = self.mute_keys(player_id);
+        let keys = keys.iter().map(String::as_str).collect::<Vec<_>>();
+
+        self.read_resource::<SharedMutes>()
+            .write()
+            .unwrap()
+            .mute(&keys, duration);
+    }
+
+    pub fn unmute_player(&mut self, player_id: usize) {
+        let keys = self.mute_keys(player_id);
+        let keys = keys.iter().map(String::as_str).collect::<Vec<_>>();
+
+        self.read_resource::<SharedMutes>()
+            .write()
+            .unwrap()
+            .unmute(&keys);
+    }
+
+    /// Whether a player ignores the chat messages of another player, by UUID
+    pub fn ignores(&self, player_id: usize, sender_uuid: &str) -> bool {
+        self.get_player_state(player_id, IGNORED_STATE)
+            .and_then(|value| value.as_array().cloned())
+            .is_some_and(|ignored| ignored.iter().any(|uuid| uuid == sender_uuid))
+    }
+
+    /// Add or remove a player, by UUID, from another player's ignore list
+    pub fn set_ignored(&mut self, player_id: usize, target_uuid: &str, ignored: bool) {
+        let mut list = self
+            .get_player_state(player_id, IGNORED_STATE)
+            .and_then(|value| value.as_array().cloned())
+            .unwrap_or_default();
+
+        list.retain(|uuid| uuid != target_uuid);
+
+        if ignored {
+            list.push(target_uuid.into());
+        }
+
+        self.set_player_state(player_id, IGNORED_STATE, list.into());
+    }
+
+    /// Add a chat command to this world, replacing any command of the same name
+    pub fn register_command(&mut self, command: Command) {
+        self.write_resource::<Commands>().register(command);
+    }
+
+    /// Parse and run a chat command (without its leading `/`), replying only to the sender
+    pub fn run_command(&mut self, player_id: usize, input: &str) {
+        let words = input.split_whitespace().collect::<Vec<_>>();
+        let role = self.role(player_id).unwrap_or(Role::Guest);
+
+        let commands = self.read_resource::<Commands>();
+
+        let command = match words.first().and_then(|name| commands.get(name)) {
+            Some(command) if command.allowed(role) => command.to_owned(),
+            Some(command) => {
+                let permission = command.permission.unwrap();
+                drop(commands);
+
+                let reason = format!("You don't have permission to {}.", permission);
+                return self.send_chat(player_id, ChatType::Error, &reason);
+            }
+            None => {
+                drop(commands);
+
+                let reason = format!("Unknown command: /{}. Try /help", input);
+                return self.send_chat(player_id, ChatType::Error, &reason);
+            }
+        };
+
+        drop(commands);
+
+        let parsed =
+            self.with_parse_context(player_id, |context| command.parse(&words[1..], context));
+
+        let result = parsed.and_then(|args| (command.handler)(self, player_id, &args));
+
+        match result {
+            Ok(Some(reply)) => self.send_chat(player_id, ChatType::Info, &reply),
+            Ok(None) => {}
+            Err(reason) => self.send_chat(player_id, ChatType::Error, &reason),
         }
+    }
+
+    /// Handles a COMPLETE message, replying with suggestions for the partial command in `text`
+    pub fn on_complete(&mut self, player_id: usize, msg: messages::Message) {
+        let role = self.role(player_id).unwrap_or(Role::Guest);
 
-        let player = player.unwrap();
+        let completions = self.with_parse_context(player_id, |context| {
+            self.read_resource::<Commands>()
+                .complete(&msg.text, role, context)
+        });
+
+        let mut new_message = create_of_type(MessageType::Complete);
+        new_message.text = msg.text;
+        new_message.completions = completions;
+
+        self.broadcast(&new_message, vec![player_id], vec![]);
+    }
+
+    /// Run a closure with what command arguments are resolved against for a player
+    fn with_parse_context<T>(&self, player_id: usize, f: impl FnOnce(&ParseContext) -> T) -> T {
+        let players = self.read_resource::<Players>();
+        let chunks = self.read_resource::<Chunks>();
+        let bodies = self.ecs().read_component::<RigidBody>();
+
+        let dimension = chunks.config.dimension as f32;
+        let origin = players
+            .get(&player_id)
+            .and_then(|player| bodies.get(player.entity))
+            .map(|body| body.get_position().scale(1.0 / dimension))
+            .unwrap_or_default();
+
+        f(&ParseContext {
+            origin,
+            players: &players,
+            registry: &chunks.registry,
+        })
+    }
+
+    /// Send a chat message to a single player instantly
+    pub fn send_chat(&mut self, player_id: usize, chat_type: ChatType, body: &str) {
+        let message = create_chat_message(MessageType::Message, chat_type, "", body);
+        self.broadcast(&message, vec![player_id], vec![]);
+    }
+
+    /// Move a player to stand on a voxel, telling its client to follow
+    pub fn teleport_player(&mut self, player_id: usize, voxel: &Vec3<i32>) -> Result<(), String> {
+        let entity = self
+            .read_resource::<Players>()
+            .get(&player_id)
+            .map(|player| player.entity)
+            .ok_or_else(|| "Player is not in this world.".to_owned())?;
+
+        let dimension = self.read_resource::<WorldConfig>().dimension as f32;
+
+        if let Some(body) = self.ecs().write_component::<RigidBody>().get_mut(entity) {
+            body.set_position(&voxel_to_standing(voxel, dimension));
+        }
+
+        let message = create_teleport_message(Some(voxel), "");
+        self.broadcast(&message, vec![player_id], vec![]);
+
+        Ok(())
+    }
+
+    /// Send a player to another world, standing on `voxel` if any
+    ///
+    /// The player's session leaves this world and joins the other one once it receives
+    /// the TELEPORT message.
+    pub fn transfer_player(
+        &mut self,
+        player_id: usize,
+        world_name: &str,
+        voxel: Option<Vec3<i32>>,
+    ) {
+        let message = create_teleport_message(voxel.as_ref(), world_name);
+        self.broadcast(&message, vec![player_id], vec![]);
+    }
+
+    /// Voxel a player is standing on
+    pub fn player_voxel(&self, player_id: usize) -> Option<Vec3<i32>> {
+        let players = self.read_resource::<Players>();
+        let entity = players.get(&player_id)?.entity;
+
+        let bodies = self.ecs().read_component::<RigidBody>();
+        let Vec3(px, py, pz) = bodies.get(entity)?.get_position();
+
+        let dimension = self.read_resource::<WorldConfig>().dimension as f32;
+
+        Some(Vec3(
+            (px / dimension).floor() as i32,
+            (py / dimension).floor() as i32 - 1,
+            (pz / dimension).floor() as i32,
+        ))
+    }
+
+    /// Set the world spawn to the voxel a player is standing on
+    pub fn set_spawn(&mut self, player_id: usize) -> Option<Vec3<i32>> {
+        let spawn = self.player_voxel(player_id)?;
+
+        self.write_resource::<Spawn>().0 = Some(spawn.to_owned());
+        self.save();
+
+        Some(spawn)
+    }
+
+    /// TEST:
+    ///
+    /// Used to test entity spawning
+    pub fn test_entity(&mut self, player_id: usize) -> Result<u32, String> {
+        let players = self.read_resource::<Players>();
+        let player = players
+            .get(&player_id)
+            .ok_or_else(|| "Player is not in this world.".to_owned())?;
 
         let bodies = self.ecs().read_component::<RigidBody>();
         let body = bodies.get(player.entity).unwrap();
@@ -584,21 +1442,99 @@ impl World {
         drop(bodies);
         drop(players);
 
-        let entities = self.read_resource::<Entities>();
-        let prototype = entities
-            .get_prototype("Test")
-            .unwrap_or_else(|| panic!("Prototype not found: Test"))
-            .clone();
+        self.spawn_entity("Test", &pos)
+    }
 
-        drop(entities);
+    /// Spawn an entity of a prototype, at a position in world units, returning its id
+    pub fn spawn_entity(&mut self, etype: &str, position: &Vec3<f32>) -> Result<u32, String> {
+        let prototype = self
+            .read_resource::<Entities>()
+            .get_prototype(etype)
+            .cloned()
+            .ok_or_else(|| format!("Prototype not found: {}", etype))?;
 
-        Entities::spawn_entity(
+        let entity = Entities::spawn_entity(
             self.ecs_mut(),
             &prototype,
-            "Test",
-            &Vec3(pos.0, pos.1, pos.2),
+            etype,
+            position,
             &Quaternion(0.0, 0.0, 0.0, 0.0),
         );
+
+        Ok(entity.id())
+    }
+
+    /// Remove a spawned entity, telling clients to drop it too
+    pub fn despawn_entity(&mut self, id: u32) -> Result<(), String> {
+        let entity = self.ecs().entities().entity(id);
+
+        // players are entities too, but not the kind that can be despawned
+        let spawned =
+            self.ecs().is_alive(entity) && self.ecs().read_component::<EType>().contains(entity);
+
+        if !spawned {
+            return Err(format!("No entity with id {}.", id));
+        }
+
+        self.ecs_mut()
+            .delete_entity(entity)
+            .map_err(|error| error.to_string())?;
+
+        self.broadcast(&create_despawn_message(&[id]), vec![], vec![]);
+
+        Ok(())
+    }
+
+    /// Send a server message to every player of this world
+    pub fn announce(&mut self, body: &str) {
+        let message = create_chat_message(MessageType::Message, ChatType::Server, "", body);
+        self.broadcast(&message, vec![], vec![]);
+    }
+
+    /// Disconnect a player, its session leaves the world once the client has been told why
+    pub fn kick_player(&mut self, player_id: usize, reason: &str) {
+        self.broadcast(&create_kick_message(reason), vec![player_id], vec![]);
+    }
+
+    /// Id of an online player, by name or UUID
+    pub fn find_player(&self, name_or_uuid: &str) -> Option<usize> {
+        self.read_resource::<Players>()
+            .iter()
+            .find(|(_, player)| {
+                player.identity.uuid == name_or_uuid
+                    || player
+                        .name
+                        .as_ref()
+                        .is_some_and(|name| name.eq_ignore_ascii_case(name_or_uuid))
+            })
+            .map(|(id, _)| *id)
+    }
+
+    /// Overview of the players in this world
+    pub fn player_summaries(&self) -> Vec<PlayerSummary> {
+        let players = self.read_resource::<Players>();
+        let roles = self.read_resource::<Roles>();
+        let bodies = self.ecs().read_component::<RigidBody>();
+
+        players
+            .iter()
+            .map(|(id, player)| {
+                let Vec3(px, py, pz) = bodies
+                    .get(player.entity)
+                    .map(|body| body.get_position())
+                    .unwrap_or_default();
+
+                PlayerSummary {
+                    id: id.to_string(),
+                    name: player.name.to_owned().unwrap_or_default(),
+                    uuid: player.identity.uuid.to_owned(),
+                    guest: player.identity.guest,
+                    role: roles.get(&player.identity),
+                    world: self.name.to_owned(),
+                    position: [px, py, pz],
+                }
+            })
+            .collect()
     }
 
     /// Sync configurations to the world's JSON file
@@ -611,11 +1547,23 @@ impl World {
         drop(chunks);
 
         if let Ok(file) = File::open(path) {
-            let WorldData { time, tick_speed } = serde_json::from_reader(file).unwrap();
+            let WorldData {
+                time,
+                tick_speed,
+                spawn,
+                weather,
+                time_locked,
+            } = serde_json::from_reader(file).unwrap();
             let mut clock = self.write_resource::<Clock>();
 
             clock.set_time(time);
             clock.set_tick_speed(tick_speed);
+            clock.locked = time_locked;
+
+            drop(clock);
+
+            self.write_resource::<Spawn>().0 = spawn;
+            *self.write_resource::<Weather>() = weather;
         }
     }
 
@@ -626,6 +1574,8 @@ impl World {
     pub fn save(&self) {
         let chunks = self.read_resource::<Chunks>();
         let clock = self.read_resource::<Clock>();
+        let spawn = self.read_resource::<Spawn>();
+        let weather = self.read_resource::<Weather>();
 
         if chunks.config.save {
             // saving world data
@@ -638,6 +1588,9 @@ impl World {
             let data = WorldData {
                 time: clock.time,
                 tick_speed: clock.tick_speed,
+                spawn: spawn.0.to_owned(),
+                weather: *weather,
+                time_locked: clock.locked,
             };
 
             let j = serde_json::to_string(&data).unwrap();
@@ -648,6 +1601,14 @@ impl World {
             // saving chunks
             chunks.save();
 
+            // saving assigned roles
+            self.read_resource::<Roles>().save();
+
+            // saving online players
+            self.read_resource::<Players>()
+                .values()
+                .for_each(|player| self.store_player(player));
+
             // info!(
             //     "Saving data for world \"{}\" took {:?}.",
             //     self.name,
@@ -682,7 +1643,8 @@ impl World {
             .with(ObserveSystem, "observe", &["search"])
             .with(EntitiesSystem, "entities", &["chunking"])
             .with(PathFindSystem, "pathfind", &["observe"])
-            .with(BroadcastSystem, "broadcast", &["peers"])
+            .with(PortalsSystem, "portals", &["peers"])
+            .with(BroadcastSystem, "broadcast", &["peers", "portals"])
             .with(WalkTowardsSystem, "walk_towards", &["pathfind"])
             .build();
 
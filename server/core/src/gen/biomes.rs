@@ -95,9 +95,20 @@ impl Default for Biomes {
 impl Biomes {
     /// https://www.desmos.com/calculator/vjrxi1kyh7
     pub fn new() -> Self {
-        let biome_configs: BiomeConfigs =
-            serde_json::from_reader(File::open("assets/metadata/biomes.json").unwrap()).unwrap();
+        Self::load().unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Load the biomes from `biomes.json`, failing if it's malformed
+    pub fn load() -> Result<Self, String> {
+        let file = File::open("assets/metadata/biomes.json").map_err(|e| e.to_string())?;
+        let biome_configs: BiomeConfigs = serde_json::from_reader(file)
+            .map_err(|error| format!("Malformed biomes.json: {}", error))?;
+
+        Ok(Self::from_configs(biome_configs))
+    }
 
+    /// Build the biomes of already parsed configs
+    pub fn from_configs(biome_configs: BiomeConfigs) -> Self {
         let BiomeConfigs {
             temperature_scale,
             temperature_seed,
@@ -12,6 +12,9 @@ use server_common::{
     vec::Vec3,
 };
 
+/// Generation types a world can be created with
+pub const GENERATORS: [&str; 2] = ["flat", "hilly"];
+
 pub struct Generator;
 
 impl Generator {
@@ -79,6 +79,29 @@ impl Lights {
         (light & 0xFFF0) | (level)
     }
 
+    /// Red, green and blue of a light with sunlight at `intensity` blended in, each from 0 to 15.
+    /// Matches how clients shade unmixed lights.
+    pub fn mix(light: u32, intensity: f32) -> [u32; 3] {
+        let share = |level: u32| level as f32 / 15.0;
+        let sun = (share(Lights::extract_sunlight(light)) * intensity * 0.8).max(0.02);
+
+        [
+            Lights::extract_red_light(light),
+            Lights::extract_green_light(light),
+            Lights::extract_blue_light(light),
+        ]
+        .map(|torch| ((sun + share(torch)).min(1.0) * 15.0).round() as u32)
+    }
+
+    /// Pack the mixed lights of a vertex by day and by night, flagged in bit 24 so clients
+    /// blend between the two instead of mixing the channels themselves
+    pub fn pack_mixed(day: [u32; 3], night: [u32; 3]) -> u32 {
+        let [dr, dg, db] = day;
+        let [nr, ng, nb] = night;
+
+        1 << 24 | dr << 20 | dg << 16 | db << 12 | nr << 8 | ng << 4 | nb
+    }
+
     /// Getter for sunlight by arbitrary coordinates
     fn get_sunlight(lights: &Ndarray<u32>, x: i32, y: i32, z: i32) -> u32 {
         let x = x as usize;
@@ -218,6 +241,33 @@ impl Lights {
         }
     }
 
+    /// Whether a block lets light through, but not all of it
+    #[inline]
+    pub fn dims(block: &Block) -> bool {
+        block.is_transparent && (block.light_reduction > 0 || block.light_filter.is_some())
+    }
+
+    /// Level of a light once it enters a block, lowered by the block's light reduction
+    /// and dimmed by its light filter
+    #[inline]
+    pub fn pass(level: u32, block: &Block, color: &LightColor) -> u32 {
+        let level = level.saturating_sub(block.light_reduction);
+
+        let [r, g, b] = match block.light_filter {
+            Some(filter) => filter,
+            None => return level,
+        };
+
+        let share = match color {
+            LightColor::Red => r,
+            LightColor::Green => g,
+            LightColor::Blue => b,
+            LightColor::None => (r + g + b) / 3.0,
+        };
+
+        (level as f32 * share.clamp(0.0, 1.0)).floor() as u32
+    }
+
     /// Remove a light source. Steps:
     ///
     /// 1. Remove the existing lights in a flood-fill fashion
@@ -333,9 +383,9 @@ impl Lights {
                 let nvx = vx + ox;
                 let nvz = vz + oz;
                 let sd = is_sunlight && *oy == -1 && level == max_light_level;
-                let nl = level - if sd { 0 } else { 1 };
                 let n_voxel = Vec3(nvx, nvy, nvz);
                 let block_type = chunks.get_block_by_voxel(nvx, nvy, nvz);
+                let nl = Lights::pass(level - if sd { 0 } else { 1 }, block_type, color);
 
                 if !block_type.is_transparent
                     || (if is_sunlight {
@@ -401,10 +451,10 @@ impl Lights {
                 }
 
                 let sd = is_sunlight && *oy == -1 && level == max_light_level;
-                let nl = level - if sd { 0 } else { 1 };
                 let n_voxel = Vec3(nvx, nvy, nvz);
                 let block_type =
                     registry.get_block_by_id(space.get_voxel(nvx + start_x, nvy, nvz + start_z));
+                let nl = Lights::pass(level - if sd { 0 } else { 1 }, block_type, color);
 
                 if !block_type.is_transparent
                     || (if is_sunlight {
@@ -457,9 +507,27 @@ impl Lights {
 
         let &Vec3(start_x, _, start_z) = min;
 
+        // where sunlight stops falling straight down, on the height map or on the highest
+        // block dimming it, whichever comes first
+        let mut sky_heights = ndarray(vec![*width, *width], 0);
+
+        for z in 0..*width as i32 {
+            for x in 0..*width as i32 {
+                let h = space.get_max_height(x + start_x, z + start_z);
+
+                sky_heights[&[x as usize, z as usize]] = (h + 1..max_height)
+                    .rev()
+                    .find(|&y| {
+                        let id = space.get_voxel(x + start_x, y as i32, z + start_z);
+                        Lights::dims(registry.get_block_by_id(id))
+                    })
+                    .unwrap_or(h);
+            }
+        }
+
         for z in 1..(width - 1) as i32 {
             for x in 1..(width - 1) as i32 {
-                let h = space.get_max_height(x + start_x, z + start_z) as i32;
+                let h = sky_heights[&[x as usize, z as usize]] as i32;
 
                 for y in (0..max_height as i32).rev() {
                     let id = space.get_voxel(x + start_x, y, z + start_z);
@@ -475,6 +543,16 @@ impl Lights {
                     if y > h && is_transparent {
                         Lights::set_sunlight(&mut lights, x, y, z, max_light_level);
 
+                        let below_id = space.get_voxel(x + start_x, y - 1, z + start_z);
+
+                        if y == h + 1 && Lights::dims(registry.get_block_by_id(below_id)) {
+                            // sunlight needs to shine down into the block dimming it
+                            sunlight_queue.push_back(LightNode {
+                                level: max_light_level,
+                                voxel: Vec3(x, y, z),
+                            });
+                        }
+
                         for [ox, oz] in CHUNK_HORIZONTAL_NEIGHBORS.iter() {
                             let neighbor_id =
                                 space.get_voxel(x + ox + start_x, y, z + oz + start_z);
@@ -484,7 +562,7 @@ impl Lights {
                                 continue;
                             }
 
-                            if space.get_max_height(x + ox + start_x, z + oz + start_z) > y as u32 {
+                            if sky_heights[&[(x + ox) as usize, (z + oz) as usize]] > y as u32 {
                                 // means sunlight should propagate here horizontally
                                 if !sunlight_queue.iter().any(|LightNode { voxel, .. }| {
                                     voxel.0 == x && voxel.1 == y && voxel.2 == z
@@ -596,3 +674,283 @@ impl Lights {
         Lights::propagate(&space, registry, config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use serde_json::{json, Value};
+
+    use server_common::vec::Vec2;
+
+    use super::super::super::engine::chunk::Chunk;
+    use super::super::biomes::{BiomeConfigs, Biomes};
+    use super::*;
+
+    const AIR: u32 = 0;
+    const STONE: u32 = 1;
+    const LEAVES: u32 = 2;
+    const WATER: u32 = 3;
+    const LAMP: u32 = 4;
+
+    /// Inner corners of the room the tests build in, walled in so light never leaves the loaded chunks
+    const MIN: i32 = -3;
+    const MAX: i32 = 6;
+
+    fn block(name: &str, is_transparent: bool, light_reduction: u32) -> Block {
+        serde_json::from_value(json!({
+            "name": name,
+            "rotatable": false,
+            "yRotatable": false,
+            "isBlock": true,
+            "isEmpty": false,
+            "isFluid": false,
+            "isLight": false,
+            "isPlant": false,
+            "isSolid": !is_transparent,
+            "isTransparent": is_transparent,
+            "redLightLevel": 0,
+            "greenLightLevel": 0,
+            "blueLightLevel": 0,
+            "isPlantable": false,
+            "transparentStandalone": false,
+            "lightReduction": light_reduction,
+        }))
+        .unwrap()
+    }
+
+    fn emission(block: &Block, color: &LightColor) -> u32 {
+        match color {
+            LightColor::Red => block.red_light_level,
+            LightColor::Green => block.green_light_level,
+            LightColor::Blue => block.blue_light_level,
+            LightColor::None => 0,
+        }
+    }
+
+    /// A room open to the sky, lit from scratch
+    fn world() -> Chunks {
+        let mut lamp = block("Lamp", false, 0);
+        lamp.is_light = true;
+        lamp.red_light_level = 12;
+        lamp.green_light_level = 6;
+
+        let mut blocks = HashMap::new();
+        blocks.insert(AIR, block("Air", true, 0));
+        blocks.insert(STONE, block("Stone", false, 0));
+        blocks.insert(LEAVES, block("Leaves", true, 2));
+        blocks.insert(WATER, block("Water", true, 1));
+        blocks.insert(LAMP, lamp);
+
+        let worlds: Value =
+            serde_json::from_str(include_str!("../../../../assets/metadata/worlds.json")).unwrap();
+        let mut config = worlds["shared"].clone();
+        config["save"] = json!(false);
+        config["chunkSize"] = json!(4);
+        config["maxHeight"] = json!(16);
+        config["generation"] = json!("flat");
+
+        let config: WorldConfig = serde_json::from_value(config).unwrap();
+        let biomes: BiomeConfigs =
+            serde_json::from_str(include_str!("../../../../assets/metadata/biomes.json")).unwrap();
+        let mut chunks = Chunks::with_biomes(
+            "lights",
+            config,
+            Registry::from_blocks(blocks),
+            Arc::new(Biomes::from_configs(biomes)),
+        );
+
+        for cx in -2..=2 {
+            for cz in -2..=2 {
+                let mut chunk = Chunk::new(Vec2(cx, cz), &chunks.config, &chunks.chunk_folder);
+                chunk.needs_propagation = false;
+                chunks.add_chunk(chunk);
+            }
+        }
+
+        let max_height = chunks.config.max_height as i32;
+
+        for x in MIN - 1..=MAX + 1 {
+            for z in MIN - 1..=MAX + 1 {
+                let wall = !(MIN..=MAX).contains(&x) || !(MIN..=MAX).contains(&z);
+
+                for y in 0..max_height {
+                    if wall || y == 0 {
+                        chunks.set_voxel_by_voxel(x, y, z, STONE);
+                    }
+                }
+            }
+        }
+
+        relight(&mut chunks);
+
+        chunks
+    }
+
+    /// Light the room again from nothing, flooding from the sky and from every lamp
+    fn relight(chunks: &mut Chunks) {
+        let max_height = chunks.config.max_height as i32;
+        let max_light_level = chunks.config.max_light_level;
+
+        let mut sunlight = VecDeque::new();
+
+        for x in MIN..=MAX {
+            for z in MIN..=MAX {
+                for y in 0..max_height {
+                    chunks.set_sunlight(x, y, z, 0);
+
+                    for color in [LightColor::Red, LightColor::Green, LightColor::Blue] {
+                        chunks.set_torch_light(x, y, z, 0, &color);
+                    }
+                }
+
+                if chunks
+                    .get_block_by_voxel(x, max_height - 1, z)
+                    .is_transparent
+                {
+                    chunks.set_sunlight(x, max_height - 1, z, max_light_level);
+                    sunlight.push_back(LightNode {
+                        voxel: Vec3(x, max_height - 1, z),
+                        level: max_light_level,
+                    });
+                }
+            }
+        }
+
+        Lights::global_flood_light(chunks, sunlight, true, &LightColor::None);
+
+        for color in [LightColor::Red, LightColor::Green, LightColor::Blue] {
+            let mut queue = VecDeque::new();
+
+            for x in MIN..=MAX {
+                for z in MIN..=MAX {
+                    for y in 0..max_height {
+                        let level = emission(chunks.get_block_by_voxel(x, y, z), &color);
+
+                        if level > 0 {
+                            chunks.set_torch_light(x, y, z, level, &color);
+                            queue.push_back(LightNode {
+                                voxel: Vec3(x, y, z),
+                                level,
+                            });
+                        }
+                    }
+                }
+            }
+
+            Lights::global_flood_light(chunks, queue, false, &color);
+        }
+    }
+
+    fn snapshot(chunks: &Chunks) -> Vec<(Vec3<i32>, [u32; 4])> {
+        let max_height = chunks.config.max_height as i32;
+        let mut lights = vec![];
+
+        for x in MIN..=MAX {
+            for z in MIN..=MAX {
+                for y in 0..max_height {
+                    lights.push((
+                        Vec3(x, y, z),
+                        [
+                            chunks.get_sunlight(x, y, z),
+                            chunks.get_torch_light(x, y, z, &LightColor::Red),
+                            chunks.get_torch_light(x, y, z, &LightColor::Green),
+                            chunks.get_torch_light(x, y, z, &LightColor::Blue),
+                        ],
+                    ));
+                }
+            }
+        }
+
+        lights
+    }
+
+    /// Place a block, checking the lights left are the same as lighting the room from scratch
+    fn place(chunks: &mut Chunks, vx: i32, vy: i32, vz: i32, id: u32) {
+        chunks.update(vx, vy, vz, id, 0, 0);
+
+        let updated = snapshot(chunks);
+        relight(chunks);
+        let relit = snapshot(chunks);
+
+        if let Some(((voxel, lit), (_, expected))) =
+            updated.iter().zip(relit.iter()).find(|(a, b)| a != b)
+        {
+            panic!(
+                "After setting {:?} to {}, {:?} has lights {:?} instead of {:?}",
+                Vec3(vx, vy, vz),
+                id,
+                voxel,
+                lit,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn canopies_cast_shadows() {
+        let mut chunks = world();
+        let max_light_level = chunks.config.max_light_level;
+
+        place(&mut chunks, 0, 1, 0, LAMP);
+
+        for x in 0..4 {
+            for z in 0..4 {
+                place(&mut chunks, x, 6, z, LEAVES);
+            }
+        }
+
+        assert!(chunks.get_sunlight(1, 6, 1) < max_light_level);
+        assert!(chunks.get_sunlight(1, 3, 1) < max_light_level);
+
+        for x in 0..4 {
+            for z in 0..4 {
+                place(&mut chunks, x, 6, z, AIR);
+            }
+        }
+
+        assert_eq!(chunks.get_sunlight(1, 3, 1), max_light_level);
+    }
+
+    #[test]
+    fn light_fades_deeper_in_water() {
+        let mut chunks = world();
+        let max_light_level = chunks.config.max_light_level;
+
+        for y in 1..=4 {
+            for x in 0..4 {
+                for z in 0..4 {
+                    place(&mut chunks, x, y, z, WATER);
+                }
+            }
+        }
+
+        let surface = chunks.get_sunlight(1, 4, 1);
+        let bottom = chunks.get_sunlight(1, 1, 1);
+
+        assert!(surface < max_light_level);
+        assert!(bottom < surface);
+
+        for y in (1..=4).rev() {
+            for x in 0..4 {
+                for z in 0..4 {
+                    place(&mut chunks, x, y, z, AIR);
+                }
+            }
+        }
+
+        assert_eq!(chunks.get_sunlight(1, 1, 1), max_light_level);
+    }
+
+    #[test]
+    fn passes_through_filters() {
+        let mut glass = block("Glass", true, 1);
+        glass.light_filter = Some([1.0, 0.5, 0.0]);
+
+        assert_eq!(Lights::pass(10, &glass, &LightColor::Red), 9);
+        assert_eq!(Lights::pass(10, &glass, &LightColor::Green), 4);
+        assert_eq!(Lights::pass(10, &glass, &LightColor::Blue), 0);
+        assert_eq!(Lights::pass(10, &glass, &LightColor::None), 4);
+        assert_eq!(Lights::pass(0, &glass, &LightColor::Red), 0);
+    }
+}
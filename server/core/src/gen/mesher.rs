@@ -6,6 +6,7 @@ use super::super::{
     constants::{BlockFace, CornerData, CornerSimplified, PlantFace, BLOCK_FACES, PLANT_FACES},
     engine::{
         chunk::Chunk,
+        clock::{sunlight_intensity, DAY_LENGTH},
         registry::{get_texture_type, Registry},
         world::WorldConfig,
     },
@@ -48,6 +49,7 @@ impl Mesher {
         let WorldConfig {
             max_height,
             sub_chunks,
+            mix_lights,
             ..
         } = config;
 
@@ -55,6 +57,7 @@ impl Mesher {
         let mut indices = Vec::<i32>::new();
         let mut uvs = Vec::<f32>::new();
         let mut aos = Vec::<i32>::new();
+        let mut atlases = Vec::<i32>::new();
         let mut red_lights = Vec::<i32>::new();
         let mut green_lights = Vec::<i32>::new();
         let mut blue_lights = Vec::<i32>::new();
@@ -112,13 +115,16 @@ impl Mesher {
                             let [dx, dz] = [0, 0];
 
                             for PlantFace { corners, mat } in PLANT_FACES.iter() {
+                                let uv = uv_map.get(texture.get(*mat).unwrap()).unwrap();
                                 let UV {
                                     start_u,
                                     end_u,
                                     start_v,
                                     end_v,
-                                } = uv_map.get(texture.get(*mat).unwrap()).unwrap();
+                                    ..
+                                } = uv;
                                 let ndx = (positions.len() / 3) as i32;
+                                let atlas = pack_atlas(uv);
 
                                 for &CornerSimplified { pos, uv } in corners.iter() {
                                     let offset = (1.0 - plant_shrink) / 2.0;
@@ -143,6 +149,7 @@ impl Mesher {
 
                                     uvs.push(uv[0] as f32 * (end_u - start_u) + start_u);
                                     uvs.push(uv[1] as f32 * (start_v - end_v) + end_v);
+                                    atlases.push(atlas);
 
                                     sunlights.push(chunk.get_sunlight(vx, vy, vz) as i32);
                                     red_lights.push(chunk.get_red_light(vx, vy, vz) as i32);
@@ -198,18 +205,21 @@ impl Mesher {
                                         || (n_block_type.transparent_standalone
                                             && dir[0] + dir[1] + dir[2] >= 1))
                                 {
-                                    let UV {
-                                        start_u,
-                                        end_u,
-                                        start_v,
-                                        end_v,
-                                    } = if is_mat_1 {
+                                    let uv = if is_mat_1 {
                                         uv_map.get(texture.get("all").unwrap()).unwrap()
                                     } else if is_mat_3 {
                                         uv_map.get(texture.get(*mat3).unwrap()).unwrap()
                                     } else {
                                         uv_map.get(texture.get(*mat6).unwrap()).unwrap()
                                     };
+                                    let UV {
+                                        start_u,
+                                        end_u,
+                                        start_v,
+                                        end_v,
+                                        ..
+                                    } = uv;
+                                    let atlas = pack_atlas(uv);
 
                                     let ndx = (positions.len() / 3) as i32;
                                     let mut face_aos = vec![];
@@ -237,6 +247,7 @@ impl Mesher {
 
                                         uvs.push(uv[0] as f32 * (end_u - start_u) + start_u);
                                         uvs.push(uv[1] as f32 * (start_v - end_v) + end_v);
+                                        atlases.push(atlas);
 
                                         // calculating the 8 voxels around this vertex
                                         let dx = position[0].round() as i32;
@@ -586,6 +597,14 @@ impl Mesher {
             light = Lights::insert_green_light(light, *g as u32);
             light = Lights::insert_blue_light(light, *b as u32);
             light = Lights::insert_sunlight(light, *s as u32);
+
+            if *mix_lights {
+                light = Lights::pack_mixed(
+                    Lights::mix(light, sunlight_intensity(DAY_LENGTH / 2.0)),
+                    Lights::mix(light, sunlight_intensity(0.0)),
+                );
+            }
+
             lights.push(light as i32);
         }
 
@@ -595,6 +614,12 @@ impl Mesher {
             uvs,
             aos,
             lights,
+            atlases,
         })
     }
 }
+
+/// Atlas page of a texture in the low 8 bits, its animation index above
+fn pack_atlas(uv: &UV) -> i32 {
+    ((uv.animation << 8) | uv.page) as i32
+}
@@ -0,0 +1,527 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    path::PathBuf,
+    sync::RwLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use hmac::{Hmac, Mac, NewMac};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const PASSWORD_ROUNDS: u32 = 10000;
+const MAX_NAME_LENGTH: usize = 16;
+
+/// Secret shipped in the default `auth.json`, known to anyone able to forge tokens with it
+pub const PLACEHOLDER_SECRET: &str = "change-me-in-production";
+
+/// Who a session belongs to
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Identity {
+    pub uuid: String,
+    pub name: String,
+    pub guest: bool,
+}
+
+impl Identity {
+    /// A throwaway identity for unauthenticated players
+    pub fn guest() -> Self {
+        Self {
+            uuid: random_uuid(),
+            name: String::new(),
+            guest: true,
+        }
+    }
+}
+
+/// Everything that can go wrong while authenticating
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthError {
+    MissingToken,
+    InvalidToken,
+    ExpiredToken,
+    UnknownAccount,
+    WrongPassword,
+    InvalidName,
+    NameTaken,
+    NotAdmin,
+    InsecureSecret,
+    Storage(String),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::MissingToken => write!(f, "This server requires a login token."),
+            AuthError::InvalidToken => write!(f, "Login token is invalid."),
+            AuthError::ExpiredToken => write!(f, "Login token has expired."),
+            AuthError::UnknownAccount => write!(f, "Account not found."),
+            AuthError::WrongPassword => write!(f, "Wrong password."),
+            AuthError::InvalidName => write!(
+                f,
+                "Names have to be 1 to {} letters, digits or underscores.",
+                MAX_NAME_LENGTH
+            ),
+            AuthError::NameTaken => write!(f, "Name is already taken."),
+            AuthError::NotAdmin => write!(f, "This account is not an admin."),
+            AuthError::InsecureSecret => write!(
+                f,
+                "The token secret in auth.json is empty or the placeholder one, set a secret of your own."
+            ),
+            AuthError::Storage(reason) => write!(f, "Account storage error: {}", reason),
+        }
+    }
+}
+
+/// Check a player name: letters, digits and underscores only
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Anything able to turn a token into an identity
+pub trait TokenVerifier: Send + Sync {
+    fn verify(&self, token: &str) -> Result<Identity, AuthError>;
+}
+
+#[derive(Serialize, Deserialize)]
+struct TokenPayload {
+    uuid: String,
+    name: String,
+    exp: u64,
+}
+
+/// Tokens signed with a shared secret, in the form `payload.signature`
+pub struct HmacTokens {
+    secret: Vec<u8>,
+    lifetime: u64,
+}
+
+impl HmacTokens {
+    pub fn new(secret: &str, lifetime: u64) -> Self {
+        Self {
+            secret: secret.as_bytes().to_vec(),
+            lifetime,
+        }
+    }
+
+    /// Sign a token for an identity, valid for `lifetime` seconds
+    pub fn issue(&self, identity: &Identity) -> String {
+        let payload = TokenPayload {
+            uuid: identity.uuid.to_owned(),
+            name: identity.name.to_owned(),
+            exp: now() + self.lifetime,
+        };
+
+        let payload = base64::encode_config(
+            serde_json::to_vec(&payload).unwrap(),
+            base64::URL_SAFE_NO_PAD,
+        );
+
+        format!("{}.{}", payload, self.sign(&payload))
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any size")
+    }
+
+    fn sign(&self, payload: &str) -> String {
+        let mut mac = self.mac();
+        mac.update(payload.as_bytes());
+        base64::encode_config(mac.finalize().into_bytes(), base64::URL_SAFE_NO_PAD)
+    }
+}
+
+impl TokenVerifier for HmacTokens {
+    fn verify(&self, token: &str) -> Result<Identity, AuthError> {
+        let mut parts = token.split('.');
+
+        let (payload, signature) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(payload), Some(signature), None) => (payload, signature),
+            _ => return Err(AuthError::InvalidToken),
+        };
+
+        let signature = base64::decode_config(signature, base64::URL_SAFE_NO_PAD)
+            .map_err(|_| AuthError::InvalidToken)?;
+
+        let mut mac = self.mac();
+        mac.update(payload.as_bytes());
+        mac.verify(&signature)
+            .map_err(|_| AuthError::InvalidToken)?;
+
+        let payload = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
+            .map_err(|_| AuthError::InvalidToken)?;
+        let payload: TokenPayload =
+            serde_json::from_slice(&payload).map_err(|_| AuthError::InvalidToken)?;
+
+        if payload.exp < now() {
+            return Err(AuthError::ExpiredToken);
+        }
+
+        Ok(Identity {
+            uuid: payload.uuid,
+            name: payload.name,
+            guest: false,
+        })
+    }
+}
+
+/// A single account stored in the accounts file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub uuid: String,
+    pub salt: String,
+    pub hash: String,
+}
+
+/// Accounts stored as a JSON file, keyed by name, with salted password hashes
+pub struct LocalAccounts {
+    path: PathBuf,
+    accounts: HashMap<String, Account>,
+}
+
+impl LocalAccounts {
+    /// Load accounts from a file, starting empty if it doesn't exist yet
+    pub fn load(path: &str) -> Result<Self, AuthError> {
+        let path = PathBuf::from(path);
+
+        let accounts = match File::open(&path) {
+            Ok(file) => serde_json::from_reader(file)
+                .map_err(|error| AuthError::Storage(error.to_string()))?,
+            Err(_) => HashMap::new(),
+        };
+
+        Ok(Self { path, accounts })
+    }
+
+    /// Write the accounts back to their file
+    pub fn save(&self) -> Result<(), AuthError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|error| AuthError::Storage(error.to_string()))?;
+        }
+
+        let file =
+            File::create(&self.path).map_err(|error| AuthError::Storage(error.to_string()))?;
+
+        serde_json::to_writer_pretty(file, &self.accounts)
+            .map_err(|error| AuthError::Storage(error.to_string()))
+    }
+
+    /// Create a new account with a fresh UUID
+    pub fn add(&mut self, name: &str, password: &str) -> Result<Identity, AuthError> {
+        if !is_valid_name(name) {
+            return Err(AuthError::InvalidName);
+        }
+
+        if self.accounts.contains_key(name) {
+            return Err(AuthError::NameTaken);
+        }
+
+        let salt: [u8; 16] = rand::random();
+        let salt = base64::encode(salt);
+        let hash = hash_password(password, &salt);
+        let uuid = random_uuid();
+
+        self.accounts.insert(
+            name.to_owned(),
+            Account {
+                uuid: uuid.to_owned(),
+                salt,
+                hash,
+            },
+        );
+
+        Ok(Identity {
+            uuid,
+            name: name.to_owned(),
+            guest: false,
+        })
+    }
+
+    /// UUID of the account with a name
+    pub fn uuid_of(&self, name: &str) -> Option<String> {
+        self.accounts
+            .get(name)
+            .map(|account| account.uuid.to_owned())
+    }
+
+    /// Check a name and password against the stored hash
+    pub fn login(&self, name: &str, password: &str) -> Result<Identity, AuthError> {
+        let account = self.accounts.get(name).ok_or(AuthError::UnknownAccount)?;
+
+        let hash = hash_password(password, &account.salt);
+
+        if !constant_time_eq(hash.as_bytes(), account.hash.as_bytes()) {
+            return Err(AuthError::WrongPassword);
+        }
+
+        Ok(Identity {
+            uuid: account.uuid.to_owned(),
+            name: name.to_owned(),
+            guest: false,
+        })
+    }
+}
+
+/// JSON format of `auth.json`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthConfig {
+    pub required: bool,
+    pub accounts: String,
+    pub secret: String,
+    pub token_lifetime: u64,
+    /// Names or UUIDs of the accounts allowed to use the admin routes
+    #[serde(default)]
+    pub admins: Vec<String>,
+}
+
+/// Authentication layer in front of the websocket route
+///
+/// Local accounts log in for a signed token, and sessions present a token
+/// to the configured verifier. By default the verifier checks tokens signed
+/// with the same secret, so that an external login service sharing the
+/// secret works out of the box.
+pub struct Auth {
+    pub required: bool,
+    admins: Vec<String>,
+    accounts: RwLock<LocalAccounts>,
+    issuer: HmacTokens,
+    verifier: Box<dyn TokenVerifier>,
+}
+
+impl Auth {
+    pub fn new(config: AuthConfig) -> Result<Self, AuthError> {
+        let AuthConfig {
+            required,
+            accounts,
+            secret,
+            token_lifetime,
+            admins,
+        } = config;
+
+        if secret.is_empty() || secret == PLACEHOLDER_SECRET {
+            // forged tokens could pass for any account, admins included
+            if required || !admins.is_empty() {
+                return Err(AuthError::InsecureSecret);
+            }
+
+            warn!("{}", AuthError::InsecureSecret);
+        }
+
+        Ok(Self {
+            required,
+            admins,
+            accounts: RwLock::new(LocalAccounts::load(&accounts)?),
+            issuer: HmacTokens::new(&secret, token_lifetime),
+            verifier: Box::new(HmacTokens::new(&secret, token_lifetime)),
+        })
+    }
+
+    /// Load the authentication config from a JSON file
+    pub fn load(path: &str) -> Result<Self, AuthError> {
+        let file = File::open(path).map_err(|error| AuthError::Storage(error.to_string()))?;
+        let config: AuthConfig =
+            serde_json::from_reader(file).map_err(|error| AuthError::Storage(error.to_string()))?;
+
+        Self::new(config)
+    }
+
+    /// Swap out how session tokens are verified
+    pub fn set_verifier(&mut self, verifier: Box<dyn TokenVerifier>) {
+        self.verifier = verifier;
+    }
+
+    /// Log into a local account, returning a signed token
+    pub fn login(&self, name: &str, password: &str) -> Result<String, AuthError> {
+        let identity = self.accounts.read().unwrap().login(name, password)?;
+        Ok(self.issuer.issue(&identity))
+    }
+
+    /// UUID of a local account, by name
+    pub fn account_uuid(&self, name: &str) -> Option<String> {
+        self.accounts.read().unwrap().uuid_of(name)
+    }
+
+    /// Resolve the identity of a session from its optional token
+    pub fn identify(&self, token: Option<&str>) -> Result<Identity, AuthError> {
+        match token {
+            Some(token) => self.verifier.verify(token),
+            None if self.required => Err(AuthError::MissingToken),
+            None => Ok(Identity::guest()),
+        }
+    }
+
+    /// Resolve the identity behind an admin request, which has to be a listed admin
+    pub fn identify_admin(&self, token: Option<&str>) -> Result<Identity, AuthError> {
+        let identity = self
+            .verifier
+            .verify(token.ok_or(AuthError::MissingToken)?)?;
+
+        let is_admin = !identity.guest
+            && self
+                .admins
+                .iter()
+                .any(|admin| *admin == identity.uuid || *admin == identity.name);
+
+        if is_admin {
+            Ok(identity)
+        } else {
+            Err(AuthError::NotAdmin)
+        }
+    }
+}
+
+/// Salted PBKDF2-HMAC-SHA256 hash of a password, base64 encoded
+fn hash_password(password: &str, salt: &str) -> String {
+    let mut hash = [0u8; 32];
+    pbkdf2::pbkdf2::<HmacSha256>(
+        password.as_bytes(),
+        salt.as_bytes(),
+        PASSWORD_ROUNDS,
+        &mut hash,
+    );
+    base64::encode(hash)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Random version 4 UUID
+pub fn random_uuid() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// Whether a string is formatted like the UUIDs of `random_uuid`
+pub fn is_uuid(value: &str) -> bool {
+    value.len() == 36
+        && value.chars().enumerate().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards?")
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_round_trip_and_reject_tampering() {
+        let tokens = HmacTokens::new("secret", 60);
+        let identity = Identity {
+            uuid: random_uuid(),
+            name: "steve".to_owned(),
+            guest: false,
+        };
+
+        let token = tokens.issue(&identity);
+        assert_eq!(tokens.verify(&token), Ok(identity));
+
+        let other = HmacTokens::new("other secret", 60);
+        assert_eq!(other.verify(&token), Err(AuthError::InvalidToken));
+
+        let tampered = format!("x{}", token);
+        assert_eq!(tokens.verify(&tampered), Err(AuthError::InvalidToken));
+        assert_eq!(tokens.verify("garbage"), Err(AuthError::InvalidToken));
+    }
+
+    #[test]
+    fn expired_tokens_are_rejected() {
+        let tokens = HmacTokens::new("secret", 60);
+
+        let expired = TokenPayload {
+            uuid: String::new(),
+            name: String::new(),
+            exp: 0,
+        };
+        let expired = base64::encode_config(
+            serde_json::to_vec(&expired).unwrap(),
+            base64::URL_SAFE_NO_PAD,
+        );
+
+        let token = format!("{}.{}", expired, tokens.sign(&expired));
+        assert_eq!(tokens.verify(&token), Err(AuthError::ExpiredToken));
+    }
+
+    #[test]
+    fn recognizes_uuids() {
+        assert!(is_uuid(&random_uuid()));
+        assert!(!is_uuid("steve"));
+        assert!(!is_uuid("127.0.0.1"));
+    }
+
+    #[test]
+    fn placeholder_secret_is_refused_when_required() {
+        let config = AuthConfig {
+            required: true,
+            accounts: String::new(),
+            secret: PLACEHOLDER_SECRET.to_owned(),
+            token_lifetime: 60,
+            admins: vec![],
+        };
+
+        assert!(matches!(Auth::new(config), Err(AuthError::InsecureSecret)));
+    }
+
+    #[test]
+    fn local_accounts_check_passwords() {
+        let mut accounts = LocalAccounts {
+            path: PathBuf::new(),
+            accounts: HashMap::new(),
+        };
+
+        let identity = accounts.add("steve", "hunter2").unwrap();
+
+        assert_eq!(accounts.login("steve", "hunter2"), Ok(identity));
+        assert_eq!(
+            accounts.login("steve", "hunter3"),
+            Err(AuthError::WrongPassword)
+        );
+        assert_eq!(
+            accounts.login("alex", "hunter2"),
+            Err(AuthError::UnknownAccount)
+        );
+        assert_eq!(accounts.add("steve", "x"), Err(AuthError::NameTaken));
+        assert_eq!(accounts.add("no spaces", "x"), Err(AuthError::InvalidName));
+    }
+
+    #[test]
+    fn uuids_are_version_four() {
+        let uuid = random_uuid();
+
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+    }
+}
@@ -0,0 +1,121 @@
+use super::models::messages;
+
+/// Current version of `protocol/messages.proto`
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest client protocol version the server still talks to
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+pub const CAPABILITY_COMPRESSION: &str = "compression";
+pub const CAPABILITY_DELTA_CHUNKS: &str = "deltaChunks";
+pub const CAPABILITY_LOD: &str = "lod";
+
+/// Capabilities this server is able to provide
+const SERVER_CAPABILITIES: [&str; 1] = [CAPABILITY_COMPRESSION];
+
+/// Set of features negotiated between the server and a client
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Capabilities {
+    pub compression: bool,
+    pub delta_chunks: bool,
+    pub lod: bool,
+}
+
+impl Capabilities {
+    /// Build capabilities from a list of names, ignoring unknown ones
+    pub fn from_names(names: &[String]) -> Self {
+        let has = |name: &str| names.iter().any(|n| n == name);
+
+        Self {
+            compression: has(CAPABILITY_COMPRESSION),
+            delta_chunks: has(CAPABILITY_DELTA_CHUNKS),
+            lod: has(CAPABILITY_LOD),
+        }
+    }
+
+    /// Capabilities both sides agreed on
+    pub fn intersect(&self, other: &Self) -> Self {
+        Self {
+            compression: self.compression && other.compression,
+            delta_chunks: self.delta_chunks && other.delta_chunks,
+            lod: self.lod && other.lod,
+        }
+    }
+
+    /// List of capability names, as sent over the protocol
+    pub fn names(&self) -> Vec<String> {
+        let mut names = vec![];
+
+        if self.compression {
+            names.push(CAPABILITY_COMPRESSION.to_owned());
+        }
+
+        if self.delta_chunks {
+            names.push(CAPABILITY_DELTA_CHUNKS.to_owned());
+        }
+
+        if self.lod {
+            names.push(CAPABILITY_LOD.to_owned());
+        }
+
+        names
+    }
+
+    /// Capabilities supported on the server side
+    pub fn server() -> Self {
+        Self::from_names(
+            &SERVER_CAPABILITIES
+                .iter()
+                .map(|&name| name.to_owned())
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+/// Check a client handshake, returning the negotiated capabilities or
+/// a human readable reason for rejecting the client.
+pub fn negotiate(handshake: &messages::Handshake) -> Result<Capabilities, String> {
+    let version = handshake.version;
+
+    if version < MIN_PROTOCOL_VERSION || version > PROTOCOL_VERSION {
+        return Err(format!(
+            "Incompatible protocol version {}, server supports {} to {}.",
+            version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        ));
+    }
+
+    let requested = Capabilities::from_names(&handshake.capabilities);
+
+    Ok(requested.intersect(&Capabilities::server()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handshake(version: u32, capabilities: &[&str]) -> messages::Handshake {
+        messages::Handshake {
+            version,
+            capabilities: capabilities.iter().map(|&c| c.to_owned()).collect(),
+        }
+    }
+
+    #[test]
+    fn rejects_incompatible_versions() {
+        assert!(negotiate(&handshake(0, &[])).is_err());
+        assert!(negotiate(&handshake(PROTOCOL_VERSION + 1, &[])).is_err());
+    }
+
+    #[test]
+    fn negotiates_supported_capabilities() {
+        let capabilities = negotiate(&handshake(
+            PROTOCOL_VERSION,
+            &[CAPABILITY_COMPRESSION, CAPABILITY_LOD, "unknown"],
+        ))
+        .unwrap();
+
+        assert!(capabilities.compression);
+        assert!(!capabilities.lod);
+        assert_eq!(capabilities.names(), vec![CAPABILITY_COMPRESSION]);
+    }
+}
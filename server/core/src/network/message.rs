@@ -3,9 +3,12 @@ use serde::{Deserialize, Serialize};
 use actix::prelude::*;
 
 use crate::engine::entities::EntityPrototypes;
+use crate::engine::permissions::Role;
+use crate::engine::weather::Weather;
 
-use super::super::engine::registry::{Blocks, Ranges};
+use super::super::engine::registry::{Blocks, Packs, Ranges};
 
+use super::auth::Identity;
 use super::models;
 
 /// Base actor message to derive from
@@ -16,13 +19,57 @@ pub struct Message(pub models::messages::Message);
 /* -------------------------------------------------------------------------- */
 /*                             Connection Messages                            */
 /* -------------------------------------------------------------------------- */
-#[derive(MessageResponse)]
-pub struct JoinResult {
+/// Data a session needs once it has been admitted into a world
+#[derive(Debug, Clone)]
+pub struct JoinData {
     pub id: usize,
     pub time: f32,
     pub tick_speed: f32,
     pub spawn: [i32; 3],
+    pub rotation: [f32; 4],
+    pub weather: Weather,
+    /// Latest chat messages of the world, for the player to catch up on
+    pub history: Vec<models::messages::Message>,
     pub passables: Vec<u32>,
+    pub texture_pack: String,
+    pub ping_interval: u64,
+    pub ping_timeout: u64,
+}
+
+/// Reasons for a world to turn a player away
+#[derive(Debug, Clone, PartialEq)]
+pub enum JoinRejection {
+    Full,
+    UnknownWorld,
+    Banned,
+}
+
+impl std::fmt::Display for JoinRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            JoinRejection::Full => "World is full, and so is its queue.",
+            JoinRejection::UnknownWorld => "World not found.",
+            JoinRejection::Banned => "You are banned from this server.",
+        };
+
+        write!(f, "{}", reason)
+    }
+}
+
+#[derive(MessageResponse)]
+pub enum JoinResult {
+    Joined(JoinData),
+    Queued(usize),
+    Rejected(JoinRejection),
+}
+
+/// Sent to a session waiting in a world's join queue
+#[derive(Clone, Message)]
+#[rtype(result = "()")]
+pub enum QueueUpdate {
+    Position(usize),
+    Admitted(JoinData),
+    Rejected(JoinRejection),
 }
 
 #[derive(Clone, Message)]
@@ -31,6 +78,25 @@ pub struct JoinWorld {
     pub world_name: String,
     pub player_name: Option<String>,
     pub player_addr: Recipient<Message>,
+    pub queue_addr: Recipient<QueueUpdate>,
+    pub address: Option<String>,
+    pub identity: Identity,
+    // voxel to stand on, instead of the saved position or the spawn
+    pub position: Option<[i32; 3]>,
+    // whether to wait in the join queue if the world is full
+    pub queue: bool,
+}
+
+/// Why a player is leaving a world
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum LeaveReason {
+    #[default]
+    Quit,
+    TimedOut,
+    /// Moved to the world of that name
+    Transferred(String),
+    /// Kicked out by an admin
+    Kicked,
 }
 
 #[derive(Clone, Message)]
@@ -38,6 +104,17 @@ pub struct JoinWorld {
 pub struct LeaveWorld {
     pub world_name: String,
     pub player_id: usize,
+    pub reason: LeaveReason,
+}
+
+/// Move a player from one world to another within the same session, carrying
+/// its custom state along. The player stays put if the other world can't take it.
+#[derive(Clone, Message)]
+#[rtype(result = "JoinResult")]
+pub struct TransferWorld {
+    pub from_world: String,
+    pub player_id: usize,
+    pub join: JoinWorld,
 }
 
 /* -------------------------------------------------------------------------- */
@@ -66,12 +143,16 @@ pub struct Noop;
 pub struct ListWorldNames;
 
 #[derive(MessageResponse, Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct SimpleWorldData {
     pub name: String,
     pub time: f32,
     pub generation: String,
     pub description: String,
     pub players: usize,
+    pub max_players: usize,
+    pub queued: usize,
+    pub loaded: bool,
 }
 
 #[derive(MessageResponse, Deserialize, Serialize, Debug)]
@@ -90,9 +171,11 @@ pub struct FullWorldData {
     pub blocks: Blocks,
     pub ranges: Ranges,
     pub entities: EntityPrototypes,
-    pub uv_side_count: u32,
+    pub pages: u32,
     pub uv_texture_size: u32,
     pub packs: Vec<String>,
+    /// Atlas layout of each pack, named after `packs`
+    pub texture_packs: Packs,
 }
 
 #[derive(Clone, Message)]
@@ -100,5 +183,119 @@ pub struct FullWorldData {
 pub struct ListWorlds;
 
 #[derive(Clone, Message)]
-#[rtype(result = "FullWorldData")]
+#[rtype(result = "Option<FullWorldData>")]
 pub struct GetWorld(pub String);
+
+/* -------------------------------------------------------------------------- */
+/*                               Admin Messages                               */
+/* -------------------------------------------------------------------------- */
+/// An online player, as listed to admins
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerSummary {
+    pub id: String,
+    pub name: String,
+    pub uuid: String,
+    pub guest: bool,
+    pub role: Role,
+    pub world: String,
+    pub position: [f32; 3],
+}
+
+/// List the online players, of a single world if named
+#[derive(Clone, Message)]
+#[rtype(result = "Result<Vec<PlayerSummary>, String>")]
+pub struct ListPlayers(pub Option<String>);
+
+/// Disconnect an online player, by name
+#[derive(Clone, Message)]
+#[rtype(result = "Result<(), String>")]
+pub struct KickPlayer {
+    pub name: String,
+    pub reason: String,
+}
+
+/// Keep a player out of every world, by name if online or if it's a local account,
+/// by UUID or address otherwise. Online guests are banned by address, accounts by UUID.
+#[derive(Clone, Message)]
+#[rtype(result = "Result<String, String>")]
+pub struct BanPlayer {
+    pub player: String,
+    pub reason: String,
+    /// UUID of the local account named `player`, if there is one
+    pub account: Option<String>,
+}
+
+/// Lift a ban on a UUID or address
+#[derive(Clone, Message)]
+#[rtype(result = "Result<(), String>")]
+pub struct UnbanPlayer(pub String);
+
+/// Send a server chat message, to a single world if named
+#[derive(Clone, Message)]
+#[rtype(result = "Result<(), String>")]
+pub struct Announce {
+    pub world: Option<String>,
+    pub body: String,
+}
+
+/// Save a world, or every world
+#[derive(Clone, Message)]
+#[rtype(result = "Result<Vec<String>, String>")]
+pub struct SaveWorlds(pub Option<String>);
+
+/// Change the time and tick speed of a world
+#[derive(Clone, Message)]
+#[rtype(result = "Result<(), String>")]
+pub struct SetWorldTime {
+    pub world: String,
+    pub time: Option<f32>,
+    pub tick_speed: Option<f32>,
+}
+
+/// Spawn an entity of a prototype, responding with its id
+#[derive(Clone, Message)]
+#[rtype(result = "Result<u32, String>")]
+pub struct SpawnEntity {
+    pub world: String,
+    pub etype: String,
+    pub position: [f32; 3],
+}
+
+/// Remove a spawned entity by id
+#[derive(Clone, Message)]
+#[rtype(result = "Result<(), String>")]
+pub struct DespawnEntity {
+    pub world: String,
+    pub id: u32,
+}
+
+/// Define a new world from a generation type and config overrides, then load it
+#[derive(Clone, Message)]
+#[rtype(result = "Result<(), String>")]
+pub struct CreateWorld {
+    pub name: String,
+    pub generation: String,
+    pub description: String,
+    pub params: serde_json::Value,
+}
+
+/// Load a defined world that isn't running
+#[derive(Clone, Message)]
+#[rtype(result = "Result<(), String>")]
+pub struct LoadWorld(pub String);
+
+/// Save and stop a world without players
+#[derive(Clone, Message)]
+#[rtype(result = "Result<(), String>")]
+pub struct UnloadWorld(pub String);
+
+/// Unload a world, then remove its definition and saved data
+#[derive(Clone, Message)]
+#[rtype(result = "Result<(), String>")]
+pub struct DeleteWorld(pub String);
+
+/// Reload the block, biome and entity definitions of every world from disk
+#[derive(Clone, Message)]
+#[rtype(result = "Result<(), String>")]
+pub struct ReloadDefinitions;
@@ -1,3 +1,5 @@
+pub mod auth;
+pub mod handshake;
 pub mod message;
 pub mod models;
 pub mod routes;
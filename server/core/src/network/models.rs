@@ -4,7 +4,10 @@ use std::io::Cursor;
 
 use super::super::engine::chunk::Meshes;
 
-use server_common::{ndarray::Ndarray, vec::Vec3};
+use server_common::{
+    ndarray::Ndarray,
+    vec::{Vec2, Vec3},
+};
 
 /// Protobuf format for chunks
 #[derive(Debug)]
@@ -65,7 +68,6 @@ pub struct ChatProtocol {
 #[derive(Debug)]
 pub struct MessageComponents {
     pub r#type: messages::message::Type,
-    pub json: Option<String>,
     pub text: Option<String>,
     pub message: Option<ChatProtocol>,
     pub peers: Option<Vec<PeerProtocol>>,
@@ -78,7 +80,6 @@ impl MessageComponents {
     pub fn default_for(message_type: messages::message::Type) -> Self {
         Self {
             r#type: message_type,
-            json: None,
             text: None,
             message: None,
             peers: None,
@@ -95,9 +96,93 @@ pub mod messages {
 }
 
 impl messages::Message {
-    /// Parse the JSON string field to serde_json
-    pub fn parse_json(&self) -> Result<serde_json::Value, serde_json::Error> {
-        serde_json::from_str(&self.json)
+    /// Chunk coordinates of a REQUEST message
+    pub fn chunk_request(&self) -> Result<Vec2<i32>, String> {
+        match &self.request {
+            Some(messages::ChunkRequest { x, z }) => Ok(Vec2(*x, *z)),
+            None => Err("Chunk request is missing its coordinates.".to_owned()),
+        }
+    }
+
+    /// Validated `time` and `tickSpeed` of a CONFIG message
+    pub fn config_values(&self) -> Result<(Option<f32>, Option<f32>), String> {
+        let config = match &self.config {
+            Some(config) => config,
+            None => return Err("Config message is missing its values.".to_owned()),
+        };
+
+        let time = config.time.as_ref().map(|t| t.value);
+        let tick_speed = config.tick_speed.as_ref().map(|t| t.value);
+
+        if let Some(time) = time {
+            if !time.is_finite() || time < 0.0 {
+                return Err(format!("Invalid time: {}", time));
+            }
+        }
+
+        if let Some(tick_speed) = tick_speed {
+            if !tick_speed.is_finite() || tick_speed < 0.0 {
+                return Err(format!("Invalid tick speed: {}", tick_speed));
+            }
+        }
+
+        Ok((time, tick_speed))
+    }
+
+    /// Check that a client message has a known type and carries the payload
+    /// that type requires
+    pub fn validate(&self) -> Result<(), String> {
+        let message_type = match messages::message::Type::from_i32(self.r#type) {
+            Some(message_type) => message_type,
+            None => return Err(format!("Unknown message type: {}", self.r#type)),
+        };
+
+        match message_type {
+            messages::message::Type::Handshake => {
+                if self.handshake.is_none() {
+                    return Err("Handshake message is missing its data.".to_owned());
+                }
+            }
+            messages::message::Type::Request => {
+                self.chunk_request()?;
+            }
+            messages::message::Type::Config => {
+                self.config_values()?;
+            }
+            messages::message::Type::Update => {
+                if self.updates.is_empty() {
+                    return Err("Update message has no updates.".to_owned());
+                }
+            }
+            messages::message::Type::Peer => {
+                let peer = match self.peers.first() {
+                    Some(peer) => peer,
+                    None => return Err("Peer message has no peer data.".to_owned()),
+                };
+
+                let values = [
+                    peer.px, peer.py, peer.pz, peer.qx, peer.qy, peer.qz, peer.qw,
+                ];
+
+                if values.iter().any(|v| !v.is_finite()) {
+                    return Err("Peer message has invalid coordinates.".to_owned());
+                }
+            }
+            messages::message::Type::Message => {
+                if self.message.is_none() {
+                    return Err("Chat message is missing its content.".to_owned());
+                }
+            }
+            messages::message::Type::Complete => {}
+            _ => {
+                return Err(format!(
+                    "Unexpected message type from client: {:?}",
+                    message_type
+                ));
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -113,10 +198,6 @@ pub fn create_message(components: MessageComponents) -> messages::Message {
         ..Default::default()
     };
 
-    if let Some(json) = components.json {
-        message.json = json;
-    }
-
     if let Some(text) = components.text {
         message.text = text;
     }
@@ -188,6 +269,7 @@ pub fn create_message(components: MessageComponents) -> messages::Message {
                                     positions: opaque.positions.to_owned(),
                                     lights: opaque.lights.to_owned(),
                                     uvs: opaque.uvs.to_owned(),
+                                    atlases: opaque.atlases.to_owned(),
                                 }),
                                 transparent: transparent.map(|transparent| messages::Geometry {
                                     aos: transparent.aos.to_owned(),
@@ -195,6 +277,7 @@ pub fn create_message(components: MessageComponents) -> messages::Message {
                                     positions: transparent.positions.to_owned(),
                                     lights: transparent.lights.to_owned(),
                                     uvs: transparent.uvs.to_owned(),
+                                    atlases: transparent.atlases.to_owned(),
                                 }),
                             }
                         })
@@ -235,6 +318,88 @@ pub fn create_message(components: MessageComponents) -> messages::Message {
     message
 }
 
+/// Create an error message carrying a reason in its text
+pub fn create_error_message(reason: &str) -> messages::Message {
+    let mut message = create_of_type(messages::message::Type::Error);
+    message.text = reason.to_owned();
+    message
+}
+
+/// Create a config message, leaving out the values that are `None`
+pub fn create_config_message(time: Option<f32>, tick_speed: Option<f32>) -> messages::Message {
+    let mut message = create_of_type(messages::message::Type::Config);
+    message.config = Some(messages::Config {
+        time: time.map(|value| messages::FloatValue { value }),
+        tick_speed: tick_speed.map(|value| messages::FloatValue { value }),
+        ..Default::default()
+    });
+    message
+}
+
+/// Create a config message announcing a change of weather
+pub fn create_weather_message(weather: &str, skylight: f32) -> messages::Message {
+    let mut message = create_of_type(messages::message::Type::Config);
+    message.config = Some(messages::Config {
+        weather: weather.to_owned(),
+        skylight: Some(messages::FloatValue { value: skylight }),
+        ..Default::default()
+    });
+    message
+}
+
+/// Create a config message switching the client to another texture pack
+pub fn create_texture_pack_message(pack: &str) -> messages::Message {
+    let mut message = create_of_type(messages::message::Type::Config);
+    message.config = Some(messages::Config {
+        texture_pack: pack.to_owned(),
+        ..Default::default()
+    });
+    message
+}
+
+/// Create a teleport message, moving the player to stand on `voxel` in `world`
+///
+/// An empty world name means the player's current world. Without a voxel, the player
+/// spawns where it normally would in the new world.
+pub fn create_teleport_message(voxel: Option<&Vec3<i32>>, world: &str) -> messages::Message {
+    let mut message = create_of_type(messages::message::Type::Teleport);
+    message.teleport = Some(messages::Teleport {
+        voxel: voxel.map_or(vec![], |Vec3(vx, vy, vz)| vec![*vx, *vy, *vz]),
+        world: world.to_owned(),
+    });
+    message
+}
+
+/// Create a kick message, the session closes once it has passed it on to the client
+pub fn create_kick_message(reason: &str) -> messages::Message {
+    let mut message = create_of_type(messages::message::Type::Kick);
+    message.text = reason.to_owned();
+    message
+}
+
+/// Create a despawn message, telling clients to drop the entities of these ids
+pub fn create_despawn_message(ids: &[u32]) -> messages::Message {
+    let mut message = create_of_type(messages::message::Type::Despawn);
+    message.entities = ids
+        .iter()
+        .map(|id| messages::Entity {
+            id: id.to_string(),
+            ..Default::default()
+        })
+        .collect();
+    message
+}
+
+/// Create a registry message, telling clients to fetch the reloaded blocks and atlas
+///
+/// Blocks and ranges aren't sent along: clients fetch the new atlas images over HTTP
+/// anyway, and `/world` hands them the blocks, ranges and pack layouts they go with.
+pub fn create_registry_message(passables: Vec<u32>) -> messages::Message {
+    let mut message = create_of_type(messages::message::Type::Registry);
+    message.registry = Some(messages::Registry { passables });
+    message
+}
+
 /// Create a broadcast-able message
 pub fn create_chat_message(
     message_type: messages::message::Type,
@@ -1,8 +1,8 @@
 use actix::SystemService;
 use actix_files as fs;
 use actix_web::{
-    get,
-    web::{self, Query},
+    delete, get, post,
+    web::{self, Data, Json, Path, Query},
     Error, HttpRequest, HttpResponse, Result,
 };
 use actix_web_actors::ws;
@@ -12,14 +12,28 @@ use std::{
     time::{SystemTime, UNIX_EPOCH},
 };
 
-use super::{message, server::WsServer, session};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    auth::{Auth, AuthError, Identity},
+    message,
+    server::WsServer,
+    session,
+};
 
 /// Main websocket route
 pub async fn ws_route(
     req: HttpRequest,
     params: Query<HashMap<String, String>>,
     stream: web::Payload,
+    auth: Data<Auth>,
 ) -> Result<HttpResponse, Error> {
+    let identity = match auth.identify(params.get("token").map(|token| token.as_str())) {
+        Ok(identity) => identity,
+        Err(error) => return Ok(HttpResponse::Unauthorized().body(error.to_string())),
+    };
+
     let world_query = params.get("world");
 
     let world_name = match world_query {
@@ -35,12 +49,30 @@ pub async fn ws_route(
 
     let player = session::WsSession {
         world_name,
+        address: req.peer_addr().map(|addr| addr.ip().to_string()),
+        identity,
         ..Default::default()
     };
 
     ws::start(player, &req, stream)
 }
 
+/// JSON body of a login request
+#[derive(Deserialize)]
+pub struct LoginRequest {
+    pub name: String,
+    pub password: String,
+}
+
+/// Route to log into a local account, responds with a session token
+#[post("/login")]
+pub async fn login(body: Json<LoginRequest>, auth: Data<Auth>) -> Result<HttpResponse> {
+    match auth.login(&body.name, &body.password) {
+        Ok(token) => Ok(HttpResponse::Ok().json(serde_json::json!({ "token": token }))),
+        Err(error) => Ok(HttpResponse::Unauthorized().body(error.to_string())),
+    }
+}
+
 /// Main website path, serving statically built index.html
 pub async fn index() -> Result<fs::NamedFile> {
     Ok(fs::NamedFile::open("public/index.html")?)
@@ -59,13 +91,20 @@ pub async fn worlds() -> Result<HttpResponse> {
 /// Route to get specific full world data
 #[get("/world")]
 pub async fn world(params: Query<HashMap<String, String>>) -> Result<HttpResponse> {
-    let world_query = params.get("world").unwrap().to_owned();
+    let world_query = match params.get("world") {
+        Some(name) => name.to_owned(),
+        None => return Ok(HttpResponse::BadRequest().body("Missing world query.")),
+    };
+
     let world_data = WsServer::from_registry()
         .send(message::GetWorld(world_query))
         .await
         .unwrap();
 
-    Ok(HttpResponse::Ok().json(world_data))
+    match world_data {
+        Some(world_data) => Ok(HttpResponse::Ok().json(world_data)),
+        None => Ok(HttpResponse::NotFound().body("World not found.")),
+    }
 }
 
 /// Route to get time of world
@@ -74,10 +113,14 @@ pub async fn time(params: Query<HashMap<String, String>>) -> Result<HttpResponse
     let default = "testbed".to_owned();
 
     let world_query = params.get("world").unwrap_or(&default).to_owned();
-    let world_data = WsServer::from_registry()
+    let world_data = match WsServer::from_registry()
         .send(message::GetWorld(world_query))
         .await
-        .unwrap();
+        .unwrap()
+    {
+        Some(world_data) => world_data,
+        None => return Ok(HttpResponse::NotFound().body("World not found.")),
+    };
 
     Ok(HttpResponse::Ok().json(format!(
         "[{},{}]",
@@ -88,3 +131,429 @@ pub async fn time(params: Query<HashMap<String, String>>) -> Result<HttpResponse
             .as_millis()
     )))
 }
+
+/* -------------------------------------------------------------------------- */
+/*                                Admin Routes                                */
+/* -------------------------------------------------------------------------- */
+/// Check the `Authorization: Bearer <token>` header of an admin request
+fn authorize_admin(req: &HttpRequest, auth: &Auth) -> Result<Identity, HttpResponse> {
+    let token = req
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    auth.identify_admin(token).map_err(|error| match error {
+        AuthError::NotAdmin => HttpResponse::Forbidden().body(error.to_string()),
+        _ => HttpResponse::Unauthorized().body(error.to_string()),
+    })
+}
+
+/// Respond to an admin request with its result as JSON, or why it failed
+fn admin_response<T: Serialize>(result: std::result::Result<T, String>) -> HttpResponse {
+    match result {
+        Ok(value) => HttpResponse::Ok().json(value),
+        Err(reason) => HttpResponse::BadRequest().body(reason),
+    }
+}
+
+/// Route to list the online players, of a single world with the `world` query
+#[get("/admin/players")]
+pub async fn admin_players(
+    req: HttpRequest,
+    params: Query<HashMap<String, String>>,
+    auth: Data<Auth>,
+) -> Result<HttpResponse> {
+    if let Err(response) = authorize_admin(&req, &auth) {
+        return Ok(response);
+    }
+
+    let result = WsServer::from_registry()
+        .send(message::ListPlayers(params.get("world").cloned()))
+        .await
+        .unwrap();
+
+    Ok(admin_response(result))
+}
+
+/// JSON body of a kick or ban request
+#[derive(Deserialize)]
+pub struct KickRequest {
+    pub player: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Route to disconnect an online player
+#[post("/admin/kick")]
+pub async fn admin_kick(
+    req: HttpRequest,
+    body: Json<KickRequest>,
+    auth: Data<Auth>,
+) -> Result<HttpResponse> {
+    let admin = match authorize_admin(&req, &auth) {
+        Ok(admin) => admin,
+        Err(response) => return Ok(response),
+    };
+
+    let KickRequest { player, reason } = body.into_inner();
+
+    info!("{} kicked {}", admin.name, player);
+
+    let result = WsServer::from_registry()
+        .send(message::KickPlayer {
+            name: player,
+            reason: reason.unwrap_or_else(|| "Kicked by an admin.".to_owned()),
+        })
+        .await
+        .unwrap();
+
+    Ok(admin_response(result))
+}
+
+/// Route to ban a player by name if online or if it's a local account,
+/// or a UUID or address otherwise
+#[post("/admin/ban")]
+pub async fn admin_ban(
+    req: HttpRequest,
+    body: Json<KickRequest>,
+    auth: Data<Auth>,
+) -> Result<HttpResponse> {
+    let admin = match authorize_admin(&req, &auth) {
+        Ok(admin) => admin,
+        Err(response) => return Ok(response),
+    };
+
+    let KickRequest { player, reason } = body.into_inner();
+
+    info!("{} banned {}", admin.name, player);
+
+    let result = WsServer::from_registry()
+        .send(message::BanPlayer {
+            account: auth.account_uuid(&player),
+            player,
+            reason: reason.unwrap_or_else(|| "Banned by an admin.".to_owned()),
+        })
+        .await
+        .unwrap();
+
+    Ok(admin_response(result))
+}
+
+/// JSON body of an unban request
+#[derive(Deserialize)]
+pub struct UnbanRequest {
+    pub player: String,
+}
+
+/// Route to lift a ban on a UUID or address
+#[post("/admin/unban")]
+pub async fn admin_unban(
+    req: HttpRequest,
+    body: Json<UnbanRequest>,
+    auth: Data<Auth>,
+) -> Result<HttpResponse> {
+    let admin = match authorize_admin(&req, &auth) {
+        Ok(admin) => admin,
+        Err(response) => return Ok(response),
+    };
+
+    info!("{} unbanned {}", admin.name, body.player);
+
+    let result = WsServer::from_registry()
+        .send(message::UnbanPlayer(body.into_inner().player))
+        .await
+        .unwrap();
+
+    Ok(admin_response(result))
+}
+
+/// JSON body of a broadcast request, to every world without a `world`
+#[derive(Deserialize)]
+pub struct BroadcastRequest {
+    pub message: String,
+    #[serde(default)]
+    pub world: Option<String>,
+}
+
+/// Route to send a server message to the players
+#[post("/admin/broadcast")]
+pub async fn admin_broadcast(
+    req: HttpRequest,
+    body: Json<BroadcastRequest>,
+    auth: Data<Auth>,
+) -> Result<HttpResponse> {
+    if let Err(response) = authorize_admin(&req, &auth) {
+        return Ok(response);
+    }
+
+    let BroadcastRequest {
+        message,
+        world: world_name,
+    } = body.into_inner();
+
+    let result = WsServer::from_registry()
+        .send(message::Announce {
+            world: world_name,
+            body: message,
+        })
+        .await
+        .unwrap();
+
+    Ok(admin_response(result))
+}
+
+/// JSON body of a save request, saving every world without a `world`
+#[derive(Deserialize)]
+pub struct SaveRequest {
+    #[serde(default)]
+    pub world: Option<String>,
+}
+
+/// Route to save worlds, responds with the names of the saved worlds
+#[post("/admin/save")]
+pub async fn admin_save(
+    req: HttpRequest,
+    body: Json<SaveRequest>,
+    auth: Data<Auth>,
+) -> Result<HttpResponse> {
+    if let Err(response) = authorize_admin(&req, &auth) {
+        return Ok(response);
+    }
+
+    let result = WsServer::from_registry()
+        .send(message::SaveWorlds(body.into_inner().world))
+        .await
+        .unwrap();
+
+    Ok(admin_response(result))
+}
+
+/// JSON body of a time request, leaving out what shouldn't change
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeRequest {
+    pub world: String,
+    #[serde(default)]
+    pub time: Option<f32>,
+    #[serde(default)]
+    pub tick_speed: Option<f32>,
+}
+
+/// Route to change the time and tick speed of a world
+#[post("/admin/time")]
+pub async fn admin_time(
+    req: HttpRequest,
+    body: Json<TimeRequest>,
+    auth: Data<Auth>,
+) -> Result<HttpResponse> {
+    if let Err(response) = authorize_admin(&req, &auth) {
+        return Ok(response);
+    }
+
+    let TimeRequest {
+        world: world_name,
+        time: new_time,
+        tick_speed,
+    } = body.into_inner();
+
+    let result = WsServer::from_registry()
+        .send(message::SetWorldTime {
+            world: world_name,
+            time: new_time,
+            tick_speed,
+        })
+        .await
+        .unwrap();
+
+    Ok(admin_response(result))
+}
+
+/// JSON body of an entity spawn request, the position being in world units
+#[derive(Deserialize)]
+pub struct SpawnRequest {
+    pub world: String,
+    #[serde(rename = "type")]
+    pub etype: String,
+    pub position: [f32; 3],
+}
+
+/// Route to spawn an entity, responds with its id
+#[post("/admin/entities")]
+pub async fn admin_spawn(
+    req: HttpRequest,
+    body: Json<SpawnRequest>,
+    auth: Data<Auth>,
+) -> Result<HttpResponse> {
+    if let Err(response) = authorize_admin(&req, &auth) {
+        return Ok(response);
+    }
+
+    let SpawnRequest {
+        world: world_name,
+        etype,
+        position,
+    } = body.into_inner();
+
+    let result = WsServer::from_registry()
+        .send(message::SpawnEntity {
+            world: world_name,
+            etype,
+            position,
+        })
+        .await
+        .unwrap();
+
+    Ok(admin_response(
+        result.map(|id| serde_json::json!({ "id": id })),
+    ))
+}
+
+/// Route to despawn an entity of a world by id
+#[delete("/admin/entities/{world}/{id}")]
+pub async fn admin_despawn(
+    req: HttpRequest,
+    path: Path<(String, u32)>,
+    auth: Data<Auth>,
+) -> Result<HttpResponse> {
+    if let Err(response) = authorize_admin(&req, &auth) {
+        return Ok(response);
+    }
+
+    let (world_name, id) = path.into_inner();
+
+    let result = WsServer::from_registry()
+        .send(message::DespawnEntity {
+            world: world_name,
+            id,
+        })
+        .await
+        .unwrap();
+
+    Ok(admin_response(result))
+}
+
+/// JSON body of a world creation request, `params` overriding the shared world configs
+#[derive(Deserialize)]
+pub struct CreateWorldRequest {
+    pub name: String,
+    pub generation: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+/// Route to define a new world and load it
+#[post("/admin/worlds")]
+pub async fn admin_create_world(
+    req: HttpRequest,
+    body: Json<CreateWorldRequest>,
+    auth: Data<Auth>,
+) -> Result<HttpResponse> {
+    let admin = match authorize_admin(&req, &auth) {
+        Ok(admin) => admin,
+        Err(response) => return Ok(response),
+    };
+
+    let CreateWorldRequest {
+        name,
+        generation,
+        description,
+        params,
+    } = body.into_inner();
+
+    info!("{} created world {}", admin.name, name);
+
+    let result = WsServer::from_registry()
+        .send(message::CreateWorld {
+            name,
+            generation,
+            description,
+            params,
+        })
+        .await
+        .unwrap();
+
+    Ok(admin_response(result))
+}
+
+/// Route to load a defined world that isn't running
+#[post("/admin/worlds/{world}/load")]
+pub async fn admin_load_world(
+    req: HttpRequest,
+    path: Path<String>,
+    auth: Data<Auth>,
+) -> Result<HttpResponse> {
+    if let Err(response) = authorize_admin(&req, &auth) {
+        return Ok(response);
+    }
+
+    let result = WsServer::from_registry()
+        .send(message::LoadWorld(path.into_inner()))
+        .await
+        .unwrap();
+
+    Ok(admin_response(result))
+}
+
+/// Route to save and stop a world without players
+#[post("/admin/worlds/{world}/unload")]
+pub async fn admin_unload_world(
+    req: HttpRequest,
+    path: Path<String>,
+    auth: Data<Auth>,
+) -> Result<HttpResponse> {
+    if let Err(response) = authorize_admin(&req, &auth) {
+        return Ok(response);
+    }
+
+    let result = WsServer::from_registry()
+        .send(message::UnloadWorld(path.into_inner()))
+        .await
+        .unwrap();
+
+    Ok(admin_response(result))
+}
+
+/// Route to delete a world along with its saved data
+#[delete("/admin/worlds/{world}")]
+pub async fn admin_delete_world(
+    req: HttpRequest,
+    path: Path<String>,
+    auth: Data<Auth>,
+) -> Result<HttpResponse> {
+    let admin = match authorize_admin(&req, &auth) {
+        Ok(admin) => admin,
+        Err(response) => return Ok(response),
+    };
+
+    let world_name = path.into_inner();
+
+    info!("{} deleted world {}", admin.name, world_name);
+
+    let result = WsServer::from_registry()
+        .send(message::DeleteWorld(world_name))
+        .await
+        .unwrap();
+
+    Ok(admin_response(result))
+}
+
+/// Route to reload the block, biome and entity definitions without restarting
+#[post("/admin/reload")]
+pub async fn admin_reload(req: HttpRequest, auth: Data<Auth>) -> Result<HttpResponse> {
+    let admin = match authorize_admin(&req, &auth) {
+        Ok(admin) => admin,
+        Err(response) => return Ok(response),
+    };
+
+    info!("{} reloaded the definitions", admin.name);
+
+    let result = WsServer::from_registry()
+        .send(message::ReloadDefinitions)
+        .await
+        .unwrap();
+
+    Ok(admin_response(result))
+}
@@ -1,60 +1,343 @@
 use actix::prelude::*;
 use actix_broker::BrokerSubscribe;
 
-use hashbrown::HashMap;
-use std::time::Duration;
+use hashbrown::{HashMap, HashSet};
+use log::{info, warn};
+use serde_json::json;
+use server_common::vec::Vec3;
+use std::collections::VecDeque;
+use std::fs;
+use std::fs::File;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
-use crate::engine::config::Configs;
-use crate::engine::entities::Entities;
+use crate::constants::{BANS_FILE, CREATED_WORLDS_FILE, WORLDS_FILE};
+use crate::engine::chat::{ChatOutbox, Mutes, OutgoingChat, SharedMutes};
+use crate::engine::config::{Configs, WorldDefinition};
+use crate::engine::entities::{Entities, EntityPrototypes};
+use crate::engine::permissions::Permission;
+use crate::engine::registry::Registry;
 use crate::engine::world::{WorldConfig, WorldMeta};
+use crate::gen::biomes::Biomes;
+use crate::gen::generator::GENERATORS;
 
 use super::super::engine::{chunks::Chunks, clock::Clock, players::Players, world::World};
 
+use super::auth::{is_uuid, is_valid_name};
 use super::message::{
-    FullWorldData, GetWorld, JoinWorld, LeaveWorld, ListWorldNames, ListWorlds, Noop,
-    PlayerMessage, SimpleWorldData,
+    Announce, BanPlayer, CreateWorld, DeleteWorld, DespawnEntity, FullWorldData, GetWorld,
+    JoinRejection, JoinResult, JoinWorld, KickPlayer, LeaveReason, LeaveWorld, ListPlayers,
+    ListWorldNames, ListWorlds, LoadWorld, Noop, PlayerMessage, QueueUpdate, ReloadDefinitions,
+    SaveWorlds, SetWorldTime, SimpleWorldData, SpawnEntity, TransferWorld, UnbanPlayer,
+    UnloadWorld,
 };
-use super::models::{messages, messages::message::Type as MessageType};
+use super::models::messages::message::Type as MessageType;
+use super::models::ChatType;
+
+/// How often loaded worlds are checked for being idle
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
 
 #[derive(Default)]
 pub struct WsServer {
     worlds: HashMap<String, World>,
+    definitions: HashMap<String, WorldDefinition>,
+    ticks: HashMap<String, SpawnHandle>,
+    idle_since: HashMap<String, Instant>,
+    registry: Option<Registry>,
+    queues: HashMap<String, VecDeque<JoinWorld>>,
+    bans: HashSet<String>,
+    mutes: SharedMutes,
 }
 
 impl WsServer {
-    fn load_worlds(&mut self) {
-        // Loading worlds from `worlds.json`
-        let mut worlds: HashMap<String, World> = HashMap::new();
-        let (configs, registry) = Configs::load_worlds("assets/metadata/worlds.json");
-
-        configs.into_iter().for_each(|(_, (meta, config))| {
-            let mut new_world = World::new(meta, config, registry.to_owned());
-            new_world.preload();
-            worlds.insert(new_world.name.to_owned(), new_world);
+    fn load_worlds(&mut self, ctx: &mut Context<Self>) {
+        // Loading the shipped worlds and the ones created at runtime
+        let (definitions, registry) = Configs::load_worlds(WORLDS_FILE, CREATED_WORLDS_FILE);
+
+        self.registry = Some(registry);
+
+        definitions.into_iter().for_each(|(name, (meta, config))| {
+            self.start_world(meta.clone(), config.clone(), ctx);
+            self.definitions.insert(name, (meta, config));
+        });
+    }
+
+    /// Create a world from its definition and start ticking it
+    fn start_world(&mut self, meta: WorldMeta, config: WorldConfig, ctx: &mut Context<Self>) {
+        let registry = self.registry.to_owned().unwrap();
+        let tick_rate = config.server_tick_rate;
+
+        let mut world = World::new(meta, config, registry, Arc::clone(&self.mutes));
+        world.preload();
+
+        let name = world.name.to_owned();
+        let handle = ctx.run_interval(Duration::from_millis(tick_rate), {
+            let name = name.to_owned();
+            move |act, _ctx| {
+                if let Some(world) = act.worlds.get_mut(&name) {
+                    world.tick();
+                }
+
+                act.deliver_chat(&name);
+            }
         });
 
-        self.worlds = worlds;
+        self.ticks.insert(name.to_owned(), handle);
+        self.worlds.insert(name, world);
+    }
+
+    /// Load a defined world if it isn't running, re-reading its definition from `worlds.json`
+    fn load_world(&mut self, world_name: &str, ctx: &mut Context<Self>) -> Result<(), String> {
+        if self.worlds.contains_key(world_name) {
+            return Ok(());
+        }
+
+        let (meta, config) =
+            match Configs::load_world(WORLDS_FILE, CREATED_WORLDS_FILE, world_name)? {
+                Some(definition) => definition,
+                None => {
+                    self.definitions.remove(world_name);
+                    return Err(format!("World not found: {}", world_name));
+                }
+            };
+
+        self.start_world(meta.clone(), config.clone(), ctx);
+        self.definitions
+            .insert(world_name.to_owned(), (meta, config));
+
+        info!("Loaded world \"{}\".", world_name);
+
+        Ok(())
+    }
+
+    /// Load a defined world on demand, for a player about to join it
+    fn wake_world(&mut self, world_name: &str, ctx: &mut Context<Self>) {
+        if self.worlds.contains_key(world_name) || !self.definitions.contains_key(world_name) {
+            return;
+        }
+
+        if let Err(error) = self.load_world(world_name, ctx) {
+            warn!("Unable to load world \"{}\": {}", world_name, error);
+        }
+    }
+
+    /// Save a world without players and stop ticking it
+    fn unload_world(&mut self, world_name: &str, ctx: &mut Context<Self>) -> Result<(), String> {
+        let world = self
+            .worlds
+            .get(world_name)
+            .ok_or_else(|| format!("World not loaded: {}", world_name))?;
+
+        if !world.read_resource::<Players>().is_empty() {
+            return Err(format!("World \"{}\" still has players.", world_name));
+        }
+
+        world.save();
+
+        if let Some(handle) = self.ticks.remove(world_name) {
+            ctx.cancel_future(handle);
+        }
+
+        self.worlds.remove(world_name);
+        self.idle_since.remove(world_name);
+
+        info!("Unloaded world \"{}\".", world_name);
+
+        Ok(())
+    }
+
+    /// Unload the saved worlds that have been empty for longer than their `unload_after`
+    fn unload_idle(&mut self, ctx: &mut Context<Self>) {
+        let now = Instant::now();
+        let mut idle = vec![];
+
+        for world in self.worlds.values() {
+            let config = world.read_resource::<WorldConfig>();
+            let empty = world.read_resource::<Players>().is_empty();
+            let queued = self.queues.get(&world.name).is_some_and(|q| !q.is_empty());
+
+            if !empty || queued || !config.save || config.unload_after == 0 {
+                self.idle_since.remove(&world.name);
+                continue;
+            }
+
+            let since = *self.idle_since.entry(world.name.to_owned()).or_insert(now);
+
+            if now.duration_since(since) >= Duration::from_secs(config.unload_after) {
+                idle.push(world.name.to_owned());
+            }
+        }
+
+        for world_name in idle {
+            if let Err(error) = self.unload_world(&world_name, ctx) {
+                warn!("Unable to unload idle world: {}", error);
+            }
+        }
+    }
+
+    /// Load the UUIDs and addresses banned from every world
+    fn load_bans(&mut self) {
+        let bans: Vec<String> = match File::open(BANS_FILE) {
+            Ok(file) => serde_json::from_reader(file).unwrap_or_else(|error| {
+                warn!("Corrupted bans file {}: {}", BANS_FILE, error);
+                vec![]
+            }),
+            Err(_) => vec![],
+        };
+
+        self.bans = bans.into_iter().collect();
+    }
+
+    /// Whether the UUID or the address of a joining player is banned
+    fn is_banned(&self, join: &JoinWorld) -> bool {
+        let banned_address = join
+            .address
+            .as_ref()
+            .is_some_and(|address| self.bans.contains(address));
+
+        banned_address || self.bans.contains(&join.identity.uuid)
+    }
+
+    fn save_bans(&self) {
+        let mut bans = self.bans.iter().collect::<Vec<_>>();
+        bans.sort();
+
+        let result = File::create(BANS_FILE)
+            .map_err(|error| error.to_string())
+            .and_then(|file| serde_json::to_writer(file, &bans).map_err(|e| e.to_string()));
+
+        if let Err(error) = result {
+            warn!("Unable to save bans to {}: {}", BANS_FILE, error);
+        }
+    }
+
+    /// World of an admin request, or an error naming the missing world
+    fn world_mut(&mut self, world_name: &str) -> Result<&mut World, String> {
+        self.worlds
+            .get_mut(world_name)
+            .ok_or_else(|| format!("World not found: {}", world_name))
     }
 
-    fn start_worlds(&mut self, ctx: &mut Context<Self>) -> Vec<SpawnHandle> {
-        let mut processes = vec![];
+    /// World and id of an online player, by name or UUID
+    fn find_player(&self, name_or_uuid: &str) -> Option<(String, usize)> {
+        self.worlds.values().find_map(|world| {
+            world
+                .find_player(name_or_uuid)
+                .map(|id| (world.name.to_owned(), id))
+        })
+    }
 
+    /// Kick every online player connected from an address
+    fn kick_address(&mut self, address: &str, reason: &str) {
         for world in self.worlds.values_mut() {
-            let tick_rate = world.read_resource::<WorldConfig>().server_tick_rate;
-            processes.push((world.name.to_owned(), tick_rate));
+            let ids = world
+                .read_resource::<Players>()
+                .iter()
+                .filter(|(_, player)| player.address.as_deref() == Some(address))
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+
+            for id in ids {
+                world.kick_player(id, reason);
+            }
         }
+    }
 
-        let mut intervals = vec![];
+    /// Route the team, global and private chat messages a world has sent out to every world
+    fn deliver_chat(&mut self, world_name: &str) {
+        let outgoing = match self.worlds.get_mut(world_name) {
+            Some(world) => world
+                .write_resource::<ChatOutbox>()
+                .drain(..)
+                .collect::<Vec<_>>(),
+            None => return,
+        };
 
-        processes.into_iter().for_each(|(name, tick_rate)| {
-            intervals.push(
-                ctx.run_interval(Duration::from_millis(tick_rate), move |act, _ctx| {
-                    act.worlds.get_mut(&name).unwrap().tick();
-                }),
-            );
-        });
+        for chat in outgoing {
+            match chat {
+                OutgoingChat::Broadcast {
+                    team,
+                    sender_uuid,
+                    message,
+                } => self
+                    .worlds
+                    .values_mut()
+                    .for_each(|world| world.deliver_chat(team.as_deref(), &sender_uuid, &message)),
+                OutgoingChat::Private {
+                    sender_id,
+                    sender_uuid,
+                    to,
+                    message,
+                } => {
+                    let delivered = self
+                        .worlds
+                        .values_mut()
+                        .any(|world| world.deliver_private(&to, &sender_uuid, &message));
+
+                    if !delivered {
+                        if let Some(world) = self.worlds.get_mut(world_name) {
+                            let reason = format!("Nobody named \"{}\" is online.", to);
+                            world.send_chat(sender_id, ChatType::Error, &reason);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Let queued players into a world while it has room, then tell the
+    /// rest of the queue their new positions
+    fn admit_queued(&mut self, world_name: &str) {
+        let world = match self.worlds.get_mut(world_name) {
+            Some(world) => world,
+            None => return,
+        };
+
+        let queue = match self.queues.get_mut(world_name) {
+            Some(queue) => queue,
+            None => return,
+        };
+
+        while !world.is_full() {
+            let JoinWorld {
+                player_name,
+                player_addr,
+                queue_addr,
+                identity,
+                address,
+                position,
+                ..
+            } = match queue.pop_front() {
+                Some(join) => join,
+                None => break,
+            };
 
-        intervals
+            let data =
+                world.add_player(None, player_name, identity, address, player_addr, position);
+            let id = data.id;
+
+            // session went away while waiting
+            if queue_addr.do_send(QueueUpdate::Admitted(data)).is_err() {
+                world.remove_player(&id, &LeaveReason::Quit);
+            }
+        }
+
+        let mut position = 1;
+
+        queue.retain(|join| {
+            let alive = join
+                .queue_addr
+                .do_send(QueueUpdate::Position(position))
+                .is_ok();
+
+            if alive {
+                position += 1;
+            }
+
+            alive
+        });
     }
 }
 
@@ -71,17 +354,105 @@ impl Actor for WsServer {
 impl Handler<JoinWorld> for WsServer {
     type Result = MessageResult<JoinWorld>;
 
-    fn handle(&mut self, msg: JoinWorld, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: JoinWorld, ctx: &mut Self::Context) -> Self::Result {
+        if self.is_banned(&msg) {
+            return MessageResult(JoinResult::Rejected(JoinRejection::Banned));
+        }
+
+        self.wake_world(&msg.world_name, ctx);
+
+        let world = match self.worlds.get_mut(&msg.world_name) {
+            Some(world) => world,
+            None => return MessageResult(JoinResult::Rejected(JoinRejection::UnknownWorld)),
+        };
+
+        let queue = self.queues.entry(msg.world_name.to_owned()).or_default();
+
+        if queue.is_empty() && !world.is_full() {
+            let JoinWorld {
+                player_name,
+                player_addr,
+                identity,
+                address,
+                position,
+                ..
+            } = msg;
+
+            let data =
+                world.add_player(None, player_name, identity, address, player_addr, position);
+
+            return MessageResult(JoinResult::Joined(data));
+        }
+
+        if !msg.queue || queue.len() >= world.read_resource::<WorldConfig>().max_queue_size {
+            return MessageResult(JoinResult::Rejected(JoinRejection::Full));
+        }
+
+        queue.push_back(msg);
+
+        MessageResult(JoinResult::Queued(queue.len()))
+    }
+}
+
+impl Handler<TransferWorld> for WsServer {
+    type Result = MessageResult<TransferWorld>;
+
+    fn handle(&mut self, msg: TransferWorld, ctx: &mut Self::Context) -> Self::Result {
+        let TransferWorld {
+            from_world,
+            player_id,
+            join,
+        } = msg;
+
+        if self.is_banned(&join) {
+            return MessageResult(JoinResult::Rejected(JoinRejection::Banned));
+        }
+
+        self.wake_world(&join.world_name, ctx);
+
+        match self.worlds.get(&join.world_name) {
+            Some(world) if world.is_full() => {
+                return MessageResult(JoinResult::Rejected(JoinRejection::Full))
+            }
+            Some(_) => {}
+            None => return MessageResult(JoinResult::Rejected(JoinRejection::UnknownWorld)),
+        }
+
+        let mut state = HashMap::new();
+
+        if let Some(world) = self.worlds.get_mut(&from_world) {
+            if let Some(player) = world.read_resource::<Players>().get(&player_id) {
+                state.extend(player.state.clone());
+            }
+
+            let reason = LeaveReason::Transferred(join.world_name.to_owned());
+            world.remove_player(&player_id, &reason);
+        }
+
         let JoinWorld {
             world_name,
             player_name,
             player_addr,
-        } = msg;
+            identity,
+            address,
+            position,
+            ..
+        } = join;
 
-        let world = self.worlds.get_mut(&world_name).expect("World not found!");
-        let result = world.add_player(None, player_name, player_addr);
+        let world = self.worlds.get_mut(&world_name).unwrap();
+        let mut data =
+            world.add_player(None, player_name, identity, address, player_addr, position);
 
-        MessageResult(result)
+        // custom state belongs to the player, not to the world it was set in
+        state
+            .into_iter()
+            .for_each(|(key, value)| world.set_player_state(data.id, &key, value));
+
+        data.texture_pack = world.texture_pack(data.id);
+
+        self.admit_queued(&from_world);
+
+        MessageResult(JoinResult::Joined(data))
     }
 }
 
@@ -90,8 +461,10 @@ impl Handler<LeaveWorld> for WsServer {
 
     fn handle(&mut self, msg: LeaveWorld, _ctx: &mut Self::Context) {
         if let Some(world) = self.worlds.get_mut(&msg.world_name) {
-            world.remove_player(&msg.player_id);
+            world.remove_player(&msg.player_id, &msg.reason);
         }
+
+        self.admit_queued(&msg.world_name);
     }
 }
 
@@ -99,7 +472,10 @@ impl Handler<ListWorldNames> for WsServer {
     type Result = MessageResult<ListWorldNames>;
 
     fn handle(&mut self, _: ListWorldNames, _ctx: &mut Self::Context) -> Self::Result {
-        MessageResult(self.worlds.keys().cloned().collect())
+        let mut names = self.definitions.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+
+        MessageResult(names)
     }
 }
 
@@ -113,16 +489,39 @@ impl Handler<PlayerMessage> for WsServer {
             raw,
         } = msg;
 
-        let msg_type = messages::Message::r#type(&raw);
-        let world = self.worlds.get_mut(&world_name).unwrap();
+        let world = match self.worlds.get_mut(&world_name) {
+            Some(world) => world,
+            None => {
+                warn!(
+                    "Dropped message from player {} in unknown world \"{}\".",
+                    player_id, world_name
+                );
+                return;
+            }
+        };
 
-        match msg_type {
+        let message_type = match MessageType::from_i32(raw.r#type) {
+            Some(message_type) => message_type,
+            None => return world.send_error(player_id, "Unexpected message type."),
+        };
+
+        match Permission::for_message(message_type) {
+            Some(permission) if !world.has_permission(player_id, permission) => {
+                let reason = format!("You don't have permission to {}.", permission);
+                return world.send_error(player_id, &reason);
+            }
+            Some(_) => {}
+            None => return world.send_error(player_id, "Unexpected message type."),
+        }
+
+        match message_type {
             MessageType::Request => world.on_chunk_request(player_id, raw),
             MessageType::Config => world.on_config(player_id, raw),
             MessageType::Update => world.on_update(player_id, raw),
             MessageType::Peer => world.on_peer(player_id, raw),
             MessageType::Message => world.on_chat_message(player_id, raw),
-            _ => {}
+            MessageType::Complete => world.on_complete(player_id, raw),
+            _ => world.send_error(player_id, "Unexpected message type."),
         }
     }
 }
@@ -143,6 +542,7 @@ impl Handler<ListWorlds> for WsServer {
             let clock = world.read_resource::<Clock>();
             let chunks = world.read_resource::<Chunks>();
             let players = world.read_resource::<Players>();
+            let queued = self.queues.get(&world.name).map_or(0, |queue| queue.len());
 
             data.push(SimpleWorldData {
                 name: world.name.to_owned(),
@@ -150,9 +550,28 @@ impl Handler<ListWorlds> for WsServer {
                 generation: chunks.config.generation.to_owned(),
                 description: world.description.to_owned(),
                 players: players.len(),
+                max_players: chunks.config.max_players,
+                queued,
+                loaded: true,
             });
         });
 
+        self.definitions
+            .iter()
+            .filter(|(name, _)| !self.worlds.contains_key(*name))
+            .for_each(|(name, (meta, config))| {
+                data.push(SimpleWorldData {
+                    name: name.to_owned(),
+                    time: meta.time,
+                    generation: config.generation.to_owned(),
+                    description: meta.description.to_owned(),
+                    players: 0,
+                    max_players: config.max_players,
+                    queued: 0,
+                    loaded: false,
+                });
+            });
+
         data.sort_by(|a, b| a.name.partial_cmp(&b.name).unwrap());
 
         MessageResult(data)
@@ -163,41 +582,478 @@ impl Handler<GetWorld> for WsServer {
     type Result = MessageResult<GetWorld>;
 
     fn handle(&mut self, msg: GetWorld, _ctx: &mut Self::Context) -> Self::Result {
-        let world = self.worlds.get(&msg.0).expect("World not found.");
-
-        let clock = world.read_resource::<Clock>();
-        let chunks = world.read_resource::<Chunks>();
-        let entities = world.read_resource::<Entities>();
-        let meta = world.read_resource::<WorldMeta>();
-
-        let config = chunks.config.clone();
-        let registry = chunks.registry.clone();
-
-        MessageResult(FullWorldData {
-            chunk_size: config.chunk_size,
-            dimension: config.dimension,
-            max_height: config.max_height,
-            max_light_level: config.max_light_level,
-            name: world.name.to_owned(),
-            render_radius: config.render_radius,
-            save: config.save,
-            sub_chunks: config.sub_chunks,
-            tick_speed: clock.tick_speed,
-            time: clock.time,
-            blocks: registry.blocks.to_owned(),
-            ranges: registry.ranges.to_owned(),
-            entities: entities.get_all(),
-            uv_side_count: registry.uv_side_count,
-            uv_texture_size: registry.uv_texture_size,
-            packs: meta.packs.to_owned(),
-        })
+        if let Some(world) = self.worlds.get(&msg.0) {
+            let clock = world.read_resource::<Clock>();
+            let chunks = world.read_resource::<Chunks>();
+            let entities = world.read_resource::<Entities>();
+            let meta = world.read_resource::<WorldMeta>();
+
+            return MessageResult(Some(full_world_data(
+                &meta,
+                &chunks.config,
+                &chunks.registry,
+                entities.get_all(),
+                clock.time,
+                clock.tick_speed,
+            )));
+        }
+
+        // worlds that aren't loaded are described from their definition, joining them wakes them up
+        let (meta, config) = match self.definitions.get(&msg.0) {
+            Some(definition) => definition,
+            None => return MessageResult(None),
+        };
+
+        let registry = match &self.registry {
+            Some(registry) => registry,
+            None => return MessageResult(None),
+        };
+
+        let entities = Entities::load()
+            .map(|entities| entities.get_all())
+            .unwrap_or_default();
+
+        MessageResult(Some(full_world_data(
+            meta,
+            config,
+            registry,
+            entities,
+            meta.time,
+            meta.tick_speed,
+        )))
+    }
+}
+
+/// Everything clients need to know about a world before joining it
+fn full_world_data(
+    meta: &WorldMeta,
+    config: &WorldConfig,
+    registry: &Registry,
+    entities: EntityPrototypes,
+    time: f32,
+    tick_speed: f32,
+) -> FullWorldData {
+    FullWorldData {
+        chunk_size: config.chunk_size,
+        dimension: config.dimension,
+        max_height: config.max_height,
+        max_light_level: config.max_light_level,
+        name: meta.name.to_owned(),
+        render_radius: config.render_radius,
+        save: config.save,
+        sub_chunks: config.sub_chunks,
+        tick_speed,
+        time,
+        blocks: registry.blocks.to_owned(),
+        ranges: registry.ranges.to_owned(),
+        entities,
+        pages: registry.pages,
+        uv_texture_size: registry.uv_texture_size,
+        packs: meta.packs.to_owned(),
+        texture_packs: registry.packs.to_owned(),
+    }
+}
+
+impl Handler<ListPlayers> for WsServer {
+    type Result = MessageResult<ListPlayers>;
+
+    fn handle(&mut self, msg: ListPlayers, _ctx: &mut Self::Context) -> Self::Result {
+        let result = match msg.0 {
+            Some(world_name) => self
+                .world_mut(&world_name)
+                .map(|world| world.player_summaries()),
+            None => {
+                let mut players = self
+                    .worlds
+                    .values()
+                    .flat_map(|world| world.player_summaries())
+                    .collect::<Vec<_>>();
+                players.sort_by(|a, b| a.world.cmp(&b.world).then(a.name.cmp(&b.name)));
+                Ok(players)
+            }
+        };
+
+        MessageResult(result)
+    }
+}
+
+impl Handler<KickPlayer> for WsServer {
+    type Result = MessageResult<KickPlayer>;
+
+    fn handle(&mut self, msg: KickPlayer, _ctx: &mut Self::Context) -> Self::Result {
+        let (world_name, id) = match self.find_player(&msg.name) {
+            Some(found) => found,
+            None => return MessageResult(Err(format!("Player not online: {}", msg.name))),
+        };
+
+        if let Some(world) = self.worlds.get_mut(&world_name) {
+            world.kick_player(id, &msg.reason);
+        }
+
+        MessageResult(Ok(()))
+    }
+}
+
+impl Handler<BanPlayer> for WsServer {
+    type Result = MessageResult<BanPlayer>;
+
+    fn handle(&mut self, msg: BanPlayer, _ctx: &mut Self::Context) -> Self::Result {
+        let BanPlayer {
+            player,
+            reason,
+            account,
+        } = msg;
+
+        let banned = match self.find_player(&player) {
+            Some((world_name, id)) => {
+                let world = self.worlds.get_mut(&world_name).unwrap();
+
+                let (uuid, address, guest) = world
+                    .read_resource::<Players>()
+                    .get(&id)
+                    .map(|player| {
+                        (
+                            player.identity.uuid.to_owned(),
+                            player.address.to_owned(),
+                            player.identity.guest,
+                        )
+                    })
+                    .unwrap_or_default();
+
+                // guests get a new UUID on every connection, so only their address keeps them out.
+                // Accounts are banned by UUID alone, players behind the same address may be others.
+                match address.filter(|_| guest) {
+                    Some(address) => {
+                        self.kick_address(&address, &reason);
+                        self.bans.insert(address.to_owned());
+                        address
+                    }
+                    None => {
+                        world.kick_player(id, &reason);
+                        self.bans.insert(uuid.to_owned());
+                        uuid
+                    }
+                }
+            }
+            None if player.parse::<IpAddr>().is_ok() => {
+                self.kick_address(&player, &reason);
+                self.bans.insert(player.to_owned());
+                player
+            }
+            None if is_uuid(&player) => {
+                self.bans.insert(player.to_owned());
+                player
+            }
+            None => match account {
+                Some(uuid) => {
+                    self.bans.insert(uuid.to_owned());
+                    uuid
+                }
+                None => {
+                    return MessageResult(Err(
+                        "Player not online, ban by UUID or address".to_owned()
+                    ))
+                }
+            },
+        };
+
+        self.save_bans();
+
+        MessageResult(Ok(banned))
+    }
+}
+
+impl Handler<UnbanPlayer> for WsServer {
+    type Result = MessageResult<UnbanPlayer>;
+
+    fn handle(&mut self, msg: UnbanPlayer, _ctx: &mut Self::Context) -> Self::Result {
+        if !self.bans.remove(&msg.0) {
+            return MessageResult(Err(format!("Not banned: {}", msg.0)));
+        }
+
+        self.save_bans();
+
+        MessageResult(Ok(()))
+    }
+}
+
+impl Handler<Announce> for WsServer {
+    type Result = MessageResult<Announce>;
+
+    fn handle(&mut self, msg: Announce, _ctx: &mut Self::Context) -> Self::Result {
+        let result = match msg.world {
+            Some(world_name) => self
+                .world_mut(&world_name)
+                .map(|world| world.announce(&msg.body)),
+            None => {
+                self.worlds
+                    .values_mut()
+                    .for_each(|world| world.announce(&msg.body));
+                Ok(())
+            }
+        };
+
+        MessageResult(result)
+    }
+}
+
+impl Handler<SaveWorlds> for WsServer {
+    type Result = MessageResult<SaveWorlds>;
+
+    fn handle(&mut self, msg: SaveWorlds, _ctx: &mut Self::Context) -> Self::Result {
+        let result = match msg.0 {
+            Some(world_name) => self.world_mut(&world_name).map(|world| {
+                world.save();
+                vec![world.name.to_owned()]
+            }),
+            None => {
+                let mut saved = self
+                    .worlds
+                    .values()
+                    .map(|world| {
+                        world.save();
+                        world.name.to_owned()
+                    })
+                    .collect::<Vec<_>>();
+                saved.sort();
+                Ok(saved)
+            }
+        };
+
+        MessageResult(result)
+    }
+}
+
+impl Handler<SetWorldTime> for WsServer {
+    type Result = MessageResult<SetWorldTime>;
+
+    fn handle(&mut self, msg: SetWorldTime, _ctx: &mut Self::Context) -> Self::Result {
+        if msg
+            .tick_speed
+            .is_some_and(|speed| !speed.is_finite() || speed < 0.0)
+        {
+            return MessageResult(Err("Tick speed should be a positive number.".to_owned()));
+        }
+
+        if msg.time.is_some_and(|time| !time.is_finite()) {
+            return MessageResult(Err("Time should be a number.".to_owned()));
+        }
+
+        let result = self.world_mut(&msg.world).map(|world| {
+            if let Some(time) = msg.time {
+                world.set_time(time);
+            }
+
+            if let Some(tick_speed) = msg.tick_speed {
+                world.set_tick_speed(tick_speed);
+            }
+        });
+
+        MessageResult(result)
+    }
+}
+
+impl Handler<SpawnEntity> for WsServer {
+    type Result = MessageResult<SpawnEntity>;
+
+    fn handle(&mut self, msg: SpawnEntity, _ctx: &mut Self::Context) -> Self::Result {
+        let [px, py, pz] = msg.position;
+
+        let result = self
+            .world_mut(&msg.world)
+            .and_then(|world| world.spawn_entity(&msg.etype, &Vec3(px, py, pz)));
+
+        MessageResult(result)
+    }
+}
+
+impl Handler<DespawnEntity> for WsServer {
+    type Result = MessageResult<DespawnEntity>;
+
+    fn handle(&mut self, msg: DespawnEntity, _ctx: &mut Self::Context) -> Self::Result {
+        let result = self
+            .world_mut(&msg.world)
+            .and_then(|world| world.despawn_entity(msg.id));
+
+        MessageResult(result)
+    }
+}
+
+impl Handler<CreateWorld> for WsServer {
+    type Result = MessageResult<CreateWorld>;
+
+    fn handle(&mut self, msg: CreateWorld, ctx: &mut Self::Context) -> Self::Result {
+        let CreateWorld {
+            name,
+            generation,
+            description,
+            mut params,
+        } = msg;
+
+        if !is_valid_name(&name) {
+            return MessageResult(Err(format!("Invalid world name: {}", name)));
+        }
+
+        if !GENERATORS.contains(&generation.as_str()) {
+            return MessageResult(Err(format!(
+                "Unknown generation \"{}\", try {}.",
+                generation,
+                GENERATORS.join(" or ")
+            )));
+        }
+
+        if !params.is_object() {
+            params = json!({});
+        }
+
+        params["name"] = json!(name);
+        params["generation"] = json!(generation);
+        params["description"] = json!(description);
+
+        if let Err(error) = Configs::add_world(WORLDS_FILE, CREATED_WORLDS_FILE, params) {
+            return MessageResult(Err(error));
+        }
+
+        // don't leave a world that can't load behind in the created worlds
+        if let Err(error) = self.load_world(&name, ctx) {
+            if let Err(cleanup) = Configs::remove_world(CREATED_WORLDS_FILE, &name) {
+                warn!("Unable to remove world \"{}\": {}", name, cleanup);
+            }
+
+            self.definitions.remove(&name);
+
+            return MessageResult(Err(error));
+        }
+
+        MessageResult(Ok(()))
+    }
+}
+
+impl Handler<LoadWorld> for WsServer {
+    type Result = MessageResult<LoadWorld>;
+
+    fn handle(&mut self, msg: LoadWorld, ctx: &mut Self::Context) -> Self::Result {
+        if self.worlds.contains_key(&msg.0) {
+            return MessageResult(Err(format!("World already loaded: {}", msg.0)));
+        }
+
+        MessageResult(self.load_world(&msg.0, ctx))
+    }
+}
+
+impl Handler<UnloadWorld> for WsServer {
+    type Result = MessageResult<UnloadWorld>;
+
+    fn handle(&mut self, msg: UnloadWorld, ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.unload_world(&msg.0, ctx))
+    }
+}
+
+impl Handler<DeleteWorld> for WsServer {
+    type Result = MessageResult<DeleteWorld>;
+
+    fn handle(&mut self, msg: DeleteWorld, ctx: &mut Self::Context) -> Self::Result {
+        let world_name = msg.0;
+
+        match Configs::is_shipped(WORLDS_FILE, &world_name) {
+            Ok(false) => {}
+            Ok(true) => {
+                return MessageResult(Err(format!(
+                    "World \"{}\" is defined in {}, remove it there.",
+                    world_name, WORLDS_FILE
+                )))
+            }
+            Err(error) => return MessageResult(Err(error)),
+        }
+
+        if self.worlds.contains_key(&world_name) {
+            if let Err(error) = self.unload_world(&world_name, ctx) {
+                return MessageResult(Err(error));
+            }
+        }
+
+        let chunk_root = match self.definitions.get(&world_name) {
+            Some((_, config)) => config.chunk_root.to_owned(),
+            None => return MessageResult(Err(format!("World not found: {}", world_name))),
+        };
+
+        if let Err(error) = Configs::remove_world(CREATED_WORLDS_FILE, &world_name) {
+            return MessageResult(Err(error));
+        }
+
+        self.definitions.remove(&world_name);
+
+        // players waiting to join are turned away like anyone asking for an unknown world
+        if let Some(queue) = self.queues.remove(&world_name) {
+            for join in queue {
+                let _ = join
+                    .queue_addr
+                    .do_send(QueueUpdate::Rejected(JoinRejection::UnknownWorld));
+            }
+        }
+
+        let mut folder = PathBuf::from(chunk_root);
+        folder.push(&world_name);
+
+        if folder.exists() {
+            if let Err(error) = fs::remove_dir_all(&folder) {
+                return MessageResult(Err(format!(
+                    "Removed world \"{}\" but not its data: {}",
+                    world_name, error
+                )));
+            }
+        }
+
+        info!("Deleted world \"{}\".", world_name);
+
+        MessageResult(Ok(()))
+    }
+}
+
+impl Handler<ReloadDefinitions> for WsServer {
+    type Result = MessageResult<ReloadDefinitions>;
+
+    fn handle(&mut self, _: ReloadDefinitions, _ctx: &mut Self::Context) -> Self::Result {
+        let result = Configs::load_registry().and_then(|registry| {
+            // chunks store block ids, so existing blocks can't be renumbered
+            if let Some(current) = &self.registry {
+                current.check_stable(&registry)?;
+            }
+
+            let biomes = Arc::new(Biomes::load()?);
+            let entities = Entities::load()?;
+
+            // passed, write the new atlases for clients to fetch
+            registry.write_atlases()?;
+
+            for world in self.worlds.values_mut() {
+                world.reload_definitions(
+                    registry.to_owned(),
+                    Arc::clone(&biomes),
+                    entities.clone(),
+                );
+            }
+
+            self.registry = Some(registry);
+
+            info!("Reloaded block, biome and entity definitions.");
+
+            Ok(())
+        });
+
+        MessageResult(result)
     }
 }
 
 impl SystemService for WsServer {
     fn service_started(&mut self, ctx: &mut Context<Self>) {
-        self.load_worlds();
-        self.start_worlds(ctx);
+        // before any world starts, they all share the same mutes
+        self.mutes = Arc::new(RwLock::new(Mutes::load()));
+
+        self.load_worlds(ctx);
+        self.load_bans();
+
+        ctx.run_interval(IDLE_CHECK_INTERVAL, |act, ctx| act.unload_idle(ctx));
     }
 }
 
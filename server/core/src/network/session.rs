@@ -1,18 +1,29 @@
 use libflate::zlib::Encoder;
 
 use std::io::Write;
+use std::time::{Duration, Instant};
 
 use actix::prelude::*;
 use actix_broker::BrokerIssue;
 use actix_web_actors::ws;
 
-use super::super::network::models::{create_of_type, encode_message, messages};
+use super::super::network::models::{
+    create_chat_message, create_error_message, create_of_type, encode_message, messages, ChatType,
+    MessageType,
+};
 
+use super::auth::Identity;
+use super::handshake::{self, Capabilities, PROTOCOL_VERSION};
 use super::message::{self, PlayerMessage};
-use super::message::{JoinWorld, LeaveWorld};
+use super::message::{
+    JoinData, JoinResult, JoinWorld, LeaveReason, LeaveWorld, QueueUpdate, TransferWorld,
+};
 use super::models;
 use super::server::WsServer;
 
+/// How long a client has to send its handshake after connecting
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Default)]
 pub struct WsSession {
     // unique sessions id
@@ -21,6 +32,22 @@ pub struct WsSession {
     pub world_name: String,
     // name in world
     pub name: Option<String>,
+    // remote address of the client
+    pub address: Option<String>,
+    // who the client authenticated as
+    pub identity: Identity,
+    // whether the session has been admitted into its world
+    pub joined: bool,
+    // whether the client has completed the handshake
+    pub handshaken: bool,
+    // features negotiated during the handshake
+    pub capabilities: Capabilities,
+    // last time the client was heard from
+    pub heartbeat: Option<Instant>,
+    // interval that pings the client and checks for timeouts
+    pub heartbeat_handle: Option<SpawnHandle>,
+    // why the session is leaving its world
+    pub leave_reason: LeaveReason,
 }
 
 impl WsSession {
@@ -31,53 +58,81 @@ impl WsSession {
         let leave_msg = LeaveWorld {
             world_name: self.world_name.clone(),
             player_id: self.id,
+            reason: LeaveReason::Quit,
         };
 
         // issue_sync comes from having the `BrokerIssue` trait in scope
         self.issue_system_sync(leave_msg, ctx);
 
         let join_msg = JoinWorld {
-            world_name: world_name.to_owned(),
+            world_name,
             player_name: self.name.clone(),
             player_addr: ctx.address().recipient(),
+            queue_addr: ctx.address().recipient(),
+            address: self.address.clone(),
+            identity: self.identity.clone(),
+            position: None,
+            queue: true,
         };
 
         WsServer::from_registry()
             .send(join_msg)
             .into_actor(self)
-            .then(|id, act, ctx| {
-                if let Ok(result) = id {
-                    act.id = result.id;
-                    act.world_name = world_name;
-
-                    // TODO: fix this?
-                    let passables: Vec<String> =
-                        result.passables.iter().map(|&id| id.to_string()).collect();
-                    let passables = passables.join(",");
-                    let data = format!(
-                        r#"
-                    {{
-                        "id": "{}",
-                        "time": {},
-                        "tickSpeed": {},
-                        "spawn": [{}, {}, {}],
-                        "passables": {}
-                    }}
-                    "#,
-                        result.id,
-                        result.time,
-                        result.tick_speed,
-                        result.spawn[0],
-                        result.spawn[1],
-                        result.spawn[2],
-                        format!("[{}]", passables)
-                    );
-
-                    let mut message = create_of_type(messages::message::Type::Init);
-                    message.json = data;
-                    let encoded = encode_message(&message);
-
-                    ctx.binary(encoded);
+            .then(|result, act, ctx| {
+                match result {
+                    Ok(JoinResult::Joined(data)) => act.on_joined(data, ctx),
+                    Ok(JoinResult::Queued(position)) => act.on_queued(position, ctx),
+                    Ok(JoinResult::Rejected(reason)) => act.reject(&reason.to_string(), ctx),
+                    Err(_) => act.reject("Server is unavailable.", ctx),
+                }
+
+                fut::ready(())
+            })
+            .wait(ctx);
+    }
+
+    /// Moves the session into the world of a TELEPORT message, only leaving the
+    /// current world once the other one has let the player in
+    fn transfer(&mut self, message: messages::Message, ctx: &mut ws::WebsocketContext<Self>) {
+        let teleport = message.teleport.clone().unwrap_or_default();
+
+        let position = match teleport.voxel[..] {
+            [vx, vy, vz] => Some([vx, vy, vz]),
+            _ => None,
+        };
+
+        let transfer_msg = TransferWorld {
+            from_world: self.world_name.clone(),
+            player_id: self.id,
+            join: JoinWorld {
+                world_name: teleport.world.to_owned(),
+                player_name: self.name.clone(),
+                player_addr: ctx.address().recipient(),
+                queue_addr: ctx.address().recipient(),
+                address: self.address.clone(),
+                identity: self.identity.clone(),
+                position,
+                queue: false,
+            },
+        };
+
+        WsServer::from_registry()
+            .send(transfer_msg)
+            .into_actor(self)
+            .then(move |result, act, ctx| {
+                match result {
+                    Ok(JoinResult::Joined(data)) => {
+                        act.world_name = teleport.world;
+
+                        // lets the client drop the old world before INIT arrives
+                        ctx.binary(encode_message(&message));
+                        act.on_joined(data, ctx);
+                    }
+                    Ok(JoinResult::Rejected(reason)) => {
+                        let reason = format!("Unable to go to \"{}\": {}", teleport.world, reason);
+                        act.send_error(&reason, ctx);
+                    }
+                    _ => act.send_error("Server is unavailable.", ctx),
                 }
 
                 fut::ready(())
@@ -85,7 +140,136 @@ impl WsSession {
             .wait(ctx);
     }
 
+    /// Called once the world has let the session in, sends INIT to the client
+    fn on_joined(&mut self, data: JoinData, ctx: &mut ws::WebsocketContext<Self>) {
+        self.id = data.id;
+        self.joined = true;
+
+        let mut message = create_of_type(messages::message::Type::Init);
+        message.init = Some(messages::Init {
+            id: data.id.to_string(),
+            time: data.time,
+            tick_speed: data.tick_speed,
+            spawn: data.spawn.to_vec(),
+            rotation: data.rotation.to_vec(),
+            weather: data.weather.to_string(),
+            skylight: data.weather.skylight(),
+            passables: data.passables,
+            texture_pack: data.texture_pack,
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: self.capabilities.names(),
+        });
+        let encoded = encode_message(&message);
+
+        ctx.binary(encoded);
+
+        data.history
+            .iter()
+            .for_each(|message| ctx.binary(encode_message(message)));
+
+        self.start_heartbeat(
+            Duration::from_millis(data.ping_interval),
+            Duration::from_millis(data.ping_timeout),
+            ctx,
+        );
+    }
+
+    /// Tells the client its position in the world's join queue
+    fn on_queued(&mut self, position: usize, ctx: &mut ws::WebsocketContext<Self>) {
+        let body = format!("World is full, you are #{} in the queue.", position);
+        let message = create_chat_message(MessageType::Message, ChatType::Info, "", &body);
+
+        ctx.binary(encode_message(&message));
+    }
+
+    /// Ping the client every `interval`, disconnecting it if nothing has been
+    /// heard from it for longer than `timeout`
+    fn start_heartbeat(
+        &mut self,
+        interval: Duration,
+        timeout: Duration,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        if let Some(handle) = self.heartbeat_handle.take() {
+            ctx.cancel_future(handle);
+        }
+
+        self.heartbeat = Some(Instant::now());
+
+        let handle = ctx.run_interval(interval, move |act, ctx| {
+            let idle = act
+                .heartbeat
+                .map(|instant| instant.elapsed())
+                .unwrap_or_default();
+
+            if idle > timeout {
+                act.leave_reason = LeaveReason::TimedOut;
+                ctx.close(Some(ws::CloseReason {
+                    code: ws::CloseCode::Away,
+                    description: Some("Connection timed out.".to_owned()),
+                }));
+                ctx.stop();
+                return;
+            }
+
+            ctx.ping(b"");
+        });
+
+        self.heartbeat_handle = Some(handle);
+    }
+
+    /// Decode a binary frame from the client and validate its contents
+    pub fn decode_frame(bytes: &[u8]) -> Result<messages::Message, String> {
+        let message = models::decode_message(bytes)
+            .map_err(|error| format!("Malformed message: {}", error))?;
+
+        message.validate()?;
+
+        Ok(message)
+    }
+
+    /// Sends an ERROR message to the client without closing the connection
+    fn send_error(&self, reason: &str, ctx: &mut ws::WebsocketContext<Self>) {
+        ctx.binary(encode_message(&create_error_message(reason)));
+    }
+
+    /// Handles the first message of a connection, which has to be a handshake
+    fn on_handshake(&mut self, message: messages::Message, ctx: &mut ws::WebsocketContext<Self>) {
+        if messages::Message::r#type(&message) != messages::message::Type::Handshake {
+            self.reject("Expected a handshake before any other message.", ctx);
+            return;
+        }
+
+        let result = match message.handshake.as_ref() {
+            Some(data) => handshake::negotiate(data),
+            None => Err("Handshake message is missing its data.".to_owned()),
+        };
+
+        match result {
+            Ok(capabilities) => {
+                self.handshaken = true;
+                self.capabilities = capabilities;
+                self.join_world(ctx);
+            }
+            Err(reason) => self.reject(&reason, ctx),
+        }
+    }
+
+    /// Sends an ERROR message with the reason, then closes the connection
+    fn reject(&self, reason: &str, ctx: &mut ws::WebsocketContext<Self>) {
+        self.send_error(reason, ctx);
+        ctx.close(Some(ws::CloseReason {
+            code: ws::CloseCode::Policy,
+            description: Some(reason.to_owned()),
+        }));
+        ctx.stop();
+    }
+
     fn on_request(&mut self, message: messages::Message) {
+        if !self.joined {
+            return;
+        }
+
         WsServer::from_registry().do_send(PlayerMessage {
             player_id: self.id,
             world_name: self.world_name.to_owned(),
@@ -98,13 +282,22 @@ impl Actor for WsSession {
     type Context = ws::WebsocketContext<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
-        self.join_world(ctx);
+        ctx.run_later(HANDSHAKE_TIMEOUT, |act, ctx| {
+            if !act.handshaken {
+                act.reject("Handshake timed out.", ctx);
+            }
+        });
     }
 
     fn stopped(&mut self, _ctx: &mut Self::Context) {
+        if !self.handshaken {
+            return;
+        }
+
         WsServer::from_registry().do_send(LeaveWorld {
             world_name: self.world_name.clone(),
             player_id: self.id,
+            reason: self.leave_reason.clone(),
         });
     }
 }
@@ -114,9 +307,31 @@ impl Handler<message::Message> for WsSession {
 
     fn handle(&mut self, msg: message::Message, ctx: &mut Self::Context) {
         let message::Message(msg) = msg;
+
+        if msg.r#type == MessageType::Kick as i32 {
+            self.leave_reason = LeaveReason::Kicked;
+            ctx.binary(encode_message(&msg));
+            ctx.close(Some(ws::CloseReason {
+                code: ws::CloseCode::Policy,
+                description: Some(msg.text),
+            }));
+            ctx.stop();
+            return;
+        }
+
+        let other_world = msg
+            .teleport
+            .as_ref()
+            .is_some_and(|t| !t.world.is_empty() && t.world != self.world_name);
+
+        if other_world {
+            self.transfer(msg, ctx);
+            return;
+        }
+
         let encoded = encode_message(&msg);
 
-        if encoded.len() > 1024 {
+        if self.capabilities.compression && encoded.len() > 1024 {
             let mut encoder = Encoder::new(Vec::new()).unwrap();
             encoder.write_all(encoded.as_slice()).unwrap();
             let encoded = encoder.finish().into_result().unwrap();
@@ -127,6 +342,18 @@ impl Handler<message::Message> for WsSession {
     }
 }
 
+impl Handler<QueueUpdate> for WsSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: QueueUpdate, ctx: &mut Self::Context) {
+        match msg {
+            QueueUpdate::Position(position) => self.on_queued(position, ctx),
+            QueueUpdate::Admitted(data) => self.on_joined(data, ctx),
+            QueueUpdate::Rejected(reason) => self.reject(&reason.to_string(), ctx),
+        }
+    }
+}
+
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
         let msg = match msg {
@@ -137,11 +364,23 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
             Ok(msg) => msg,
         };
 
+        // any frame from the client proves the connection is alive
+        self.heartbeat = Some(Instant::now());
+
         match msg {
-            ws::Message::Binary(bytes) => {
-                let message = models::decode_message(&bytes.to_vec()).unwrap();
-                self.on_request(message);
+            ws::Message::Binary(bytes) => match Self::decode_frame(&bytes) {
+                Ok(message) if self.handshaken => self.on_request(message),
+                Ok(message) => self.on_handshake(message, ctx),
+                Err(reason) if self.handshaken => self.send_error(&reason, ctx),
+                Err(reason) => self.reject(&reason, ctx),
+            },
+            ws::Message::Text(_) => {
+                self.send_error("Only binary messages are supported.", ctx);
+            }
+            ws::Message::Ping(bytes) => {
+                ctx.pong(&bytes);
             }
+            ws::Message::Pong(_) => {}
             ws::Message::Close(reason) => {
                 ctx.close(reason);
                 ctx.stop();
@@ -150,3 +389,126 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::super::models::{create_config_message, create_of_type, encode_message, messages};
+    use super::WsSession;
+
+    fn sample_messages() -> Vec<messages::Message> {
+        let mut handshake = create_of_type(messages::message::Type::Handshake);
+        handshake.handshake = Some(messages::Handshake {
+            version: 1,
+            capabilities: vec!["compression".to_owned()],
+        });
+
+        let mut request = create_of_type(messages::message::Type::Request);
+        request.request = Some(messages::ChunkRequest { x: -3, z: 12 });
+
+        let mut peer = create_of_type(messages::message::Type::Peer);
+        peer.peers = vec![messages::Peer {
+            id: "1".to_owned(),
+            name: "steve".to_owned(),
+            px: 1.0,
+            py: 64.0,
+            pz: -2.5,
+            qw: 1.0,
+            ..Default::default()
+        }];
+
+        let mut update = create_of_type(messages::message::Type::Update);
+        update.updates = vec![messages::Update {
+            vx: 1,
+            vy: 2,
+            vz: 3,
+            r#type: 1,
+            ..Default::default()
+        }];
+
+        vec![
+            handshake,
+            request,
+            peer,
+            update,
+            create_config_message(Some(1200.0), Some(2.0)),
+        ]
+    }
+
+    #[test]
+    fn accepts_valid_frames() {
+        for message in sample_messages() {
+            let decoded = WsSession::decode_frame(&encode_message(&message)).unwrap();
+            assert_eq!(decoded, message);
+        }
+    }
+
+    #[test]
+    fn survives_random_bytes() {
+        let mut rng = StdRng::seed_from_u64(1021);
+
+        for _ in 0..10000 {
+            let len = rng.gen_range(0..256);
+            let bytes: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            let _ = WsSession::decode_frame(&bytes);
+        }
+    }
+
+    #[test]
+    fn survives_truncated_and_corrupted_frames() {
+        let mut rng = StdRng::seed_from_u64(1021);
+
+        for message in sample_messages() {
+            let encoded = encode_message(&message);
+
+            for len in 0..encoded.len() {
+                let _ = WsSession::decode_frame(&encoded[..len]);
+            }
+
+            for _ in 0..1000 {
+                let mut corrupted = encoded.clone();
+                let index = rng.gen_range(0..corrupted.len());
+                corrupted[index] ^= 1 << rng.gen_range(0..8);
+                let _ = WsSession::decode_frame(&corrupted);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_missing_payloads() {
+        let empty_types = [
+            messages::message::Type::Handshake,
+            messages::message::Type::Request,
+            messages::message::Type::Config,
+            messages::message::Type::Update,
+            messages::message::Type::Peer,
+            messages::message::Type::Message,
+        ];
+
+        for message_type in empty_types.iter() {
+            let encoded = encode_message(&create_of_type(*message_type));
+            assert!(WsSession::decode_frame(&encoded).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_server_only_types() {
+        let encoded = encode_message(&create_of_type(messages::message::Type::Init));
+        assert!(WsSession::decode_frame(&encoded).is_err());
+
+        let mut unknown = create_of_type(messages::message::Type::Request);
+        unknown.r#type = 1000;
+        assert!(WsSession::decode_frame(&encode_message(&unknown)).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let encoded = encode_message(&create_config_message(Some(f32::NAN), None));
+        assert!(WsSession::decode_frame(&encoded).is_err());
+
+        let mut peer = sample_messages().remove(2);
+        peer.peers[0].px = f32::INFINITY;
+        assert!(WsSession::decode_frame(&encode_message(&peer)).is_err());
+    }
+}
@@ -1,8 +1,11 @@
 use specs::{Entities, System, WriteExpect};
 
-use crate::engine::{
-    players::{BroadcastExt, Players},
-    world::MessagesQueue,
+use crate::{
+    engine::{
+        players::{create_leave_message, BroadcastExt, Players},
+        world::MessagesQueue,
+    },
+    network::message::LeaveReason,
 };
 
 pub struct BroadcastSystem;
@@ -17,9 +20,9 @@ impl<'a> System<'a> for BroadcastSystem {
     fn run(&mut self, data: Self::SystemData) {
         let (entities, mut messages, mut players) = data;
 
-        for (msg, include, exclude, sender) in messages.iter() {
-            // TODO: add spam detection?
+        let mut disconnected = vec![];
 
+        for (msg, include, exclude, sender) in messages.iter() {
             let include = if include.is_some() {
                 include.clone().unwrap()
             } else {
@@ -39,13 +42,35 @@ impl<'a> System<'a> for BroadcastSystem {
                 sender.to_owned(),
             );
 
-            inactives.into_iter().for_each(|player| {
+            inactives.into_iter().for_each(|(id, player)| {
                 entities
                     .delete(player.entity)
                     .expect("Unable to remove player entity.");
+
+                disconnected.push(create_leave_message(&id, &player, &LeaveReason::TimedOut));
             });
         }
 
         messages.clear();
+
+        // let everyone else know about players whose connections died, which may
+        // turn up more dead connections to announce in turn
+        while !disconnected.is_empty() {
+            let mut next = vec![];
+
+            for message in disconnected.iter() {
+                let inactives = players.broadcast(message, vec![], vec![], None);
+
+                inactives.into_iter().for_each(|(id, player)| {
+                    entities
+                        .delete(player.entity)
+                        .expect("Unable to remove player entity.");
+
+                    next.push(create_leave_message(&id, &player, &LeaveReason::TimedOut));
+                });
+            }
+
+            disconnected = next;
+        }
     }
 }
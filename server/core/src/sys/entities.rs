@@ -1,14 +1,18 @@
 use server_utils::convert::map_world_to_voxel;
-use specs::{Entities, ReadExpect, ReadStorage, System, WriteExpect};
+use specs::{Entities, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use server_common::vec::Vec3;
 
 use crate::{
     comp::{
-        curr_chunk::CurrChunk, etype::EType, rigidbody::RigidBody, target::Target,
+        curr_chunk::CurrChunk,
+        entity_sync::{EntitySnapshot, EntitySync},
+        etype::EType,
+        rigidbody::RigidBody,
+        target::Target,
         walk_towards::WalkTowards,
     },
-    engine::world::{MessagesQueue, WorldConfig},
+    engine::world::{EntityUpdateTimer, MessagesQueue, WorldConfig},
     network::models::{create_message, EntityProtocol, MessageComponents, MessageType},
 };
 
@@ -19,35 +23,53 @@ impl<'a> System<'a> for EntitiesSystem {
     type SystemData = (
         Entities<'a>,
         ReadExpect<'a, WorldConfig>,
+        WriteExpect<'a, EntityUpdateTimer>,
         WriteExpect<'a, MessagesQueue>,
         ReadStorage<'a, EType>,
         ReadStorage<'a, RigidBody>,
         ReadStorage<'a, CurrChunk>,
         ReadStorage<'a, Target>,
         ReadStorage<'a, WalkTowards>,
+        WriteStorage<'a, EntitySync>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         use specs::Join;
 
-        let (entities, configs, mut messages, types, bodies, curr_chunks, targets, walk_towards) =
-            data;
+        let (
+            entities,
+            configs,
+            mut timer,
+            mut messages,
+            types,
+            bodies,
+            curr_chunks,
+            targets,
+            walk_towards,
+            mut syncs,
+        ) = data;
+
+        // entity updates are sent at the network rate, not the simulation rate
+        if !timer.ready(configs.entity_update_rate) {
+            return;
+        }
 
         let dimension = configs.dimension;
 
         let mut entity_updates = vec![];
 
-        for (ent, etype, body, _curr_chunk, target, walk_toward) in (
+        for (ent, etype, body, _curr_chunk, target, walk_toward, sync) in (
             &*entities,
             &types,
             &bodies,
             &curr_chunks,
             &targets,
             &walk_towards,
+            &mut syncs,
         )
             .join()
         {
-            let Vec3(px, py, pz) = body.get_position();
+            let position = body.get_position();
 
             let look_target = target.get_position();
             let mut heading: Option<Vec3<f32>> = walk_toward.0.as_ref().map(|nodes| {
@@ -64,21 +86,38 @@ impl<'a> System<'a> for EntitiesSystem {
 
             if let Some(h) = &heading {
                 let h_pos = map_world_to_voxel(h.0, h.1, h.2, dimension);
-                let b_pos = map_world_to_voxel(px, py, pz, dimension);
+                let b_pos = map_world_to_voxel(position.0, position.1, position.2, dimension);
                 if h_pos == b_pos {
                     heading = None;
                 }
             }
 
+            let snapshot = EntitySnapshot::quantized(
+                &position,
+                &heading,
+                &look_target,
+                configs.entity_position_precision,
+                configs.entity_direction_precision,
+            );
+
+            // stationary entities still get the occasional keyframe
+            if !sync.should_send(&snapshot, configs.entity_keyframe_interval) {
+                continue;
+            }
+
+            let Vec3(px, py, pz) = snapshot.position.clone();
+
             entity_updates.push(EntityProtocol {
                 id: ent.id().to_string(),
                 r#type: etype.0.to_owned(),
-                look_at: look_target,
-                heading,
+                look_at: snapshot.look_at.clone(),
+                heading: snapshot.heading.clone(),
                 px,
                 py,
                 pz,
-            })
+            });
+
+            sync.mark_sent(snapshot);
         }
 
         if !entity_updates.is_empty() {
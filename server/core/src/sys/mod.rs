@@ -7,6 +7,7 @@ mod observe;
 mod pathfind;
 mod peers;
 mod physics;
+mod portals;
 mod search;
 mod walk_towards;
 
@@ -19,5 +20,6 @@ pub use observe::ObserveSystem;
 pub use pathfind::PathFindSystem;
 pub use peers::PeersSystem;
 pub use physics::PhysicsSystem;
+pub use portals::PortalsSystem;
 pub use search::SearchSystem;
 pub use walk_towards::WalkTowardsSystem;
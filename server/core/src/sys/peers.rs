@@ -16,6 +16,7 @@ use crate::{
         players::{PlayerUpdates, Players},
         world::MessagesQueue,
     },
+    network::auth::is_valid_name,
     network::models::{
         create_chat_message, create_message, create_of_type, messages, ChatType, MessageComponents,
         MessageType, PeerProtocol,
@@ -58,8 +59,7 @@ impl<'a> System<'a> for PeersSystem {
         for (id, name, body, rotation) in (&ids, &mut names, &mut bodies, &mut rotations).join() {
             if let Some(update) = updates.remove(&id.0) {
                 let messages::Peer {
-                    id: peer_id,
-                    name: new_name,
+                    name: requested_name,
                     px,
                     py,
                     pz,
@@ -70,10 +70,12 @@ impl<'a> System<'a> for PeersSystem {
                     ..
                 } = update;
 
+                let new_name = resolve_name(&players, id.0, &name.0, requested_name);
+
                 peers_update.insert(
                     id.0,
                     PeerProtocol {
-                        id: peer_id,
+                        id: id.0.to_string(),
                         name: new_name.clone(),
                         px,
                         py,
@@ -108,7 +110,9 @@ impl<'a> System<'a> for PeersSystem {
                 let voxel = map_world_to_voxel(px, py, pz, chunks.config.dimension);
                 let biome = chunks.biomes.get_biome(voxel.0, voxel.2);
                 let mut new_message = create_of_type(MessageType::Info);
-                new_message.json = format!("{{\"biome\": \"{}\"}}", biome.name);
+                new_message.info = Some(messages::Info {
+                    biome: biome.name.to_owned(),
+                });
                 messages.push((new_message, Some(vec![id.0]), None, None));
 
                 if let Some(player) = players.get_mut(&id.0) {
@@ -136,3 +140,38 @@ impl<'a> System<'a> for PeersSystem {
         }
     }
 }
+
+/// Decide which name a player ends up with. Authenticated players always go by
+/// their account name, guests get to pick a valid name once. Guest names are
+/// prefixed with `Guest-`, which account names can't contain, so guests can't
+/// pass for an account whether it's online or not.
+fn resolve_name(
+    players: &Players,
+    player_id: usize,
+    current: &Option<String>,
+    requested: String,
+) -> String {
+    let player = players.get(&player_id);
+
+    if let Some(player) = player {
+        if !player.identity.guest {
+            return player.identity.name.to_owned();
+        }
+    }
+
+    if let Some(current) = current {
+        return current.to_owned();
+    }
+
+    let name = format!("Guest-{}", requested);
+
+    let taken = players
+        .iter()
+        .any(|(&id, other)| id != player_id && other.name.as_deref() == Some(name.as_str()));
+
+    if is_valid_name(&requested) && !taken {
+        name
+    } else {
+        format!("Guest-{}", player_id % 10000)
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+
+use specs::{ReadExpect, System, WriteExpect, WriteStorage};
+
+use server_common::vec::Vec3;
+
+use crate::{
+    comp::rigidbody::RigidBody,
+    engine::{
+        chunks::Chunks,
+        players::Players,
+        world::{voxel_to_standing, InPortals, MessagesQueue},
+    },
+    network::models::create_teleport_message,
+};
+
+/// Sends players walking into a portal block to wherever it leads
+pub struct PortalsSystem;
+
+impl<'a> System<'a> for PortalsSystem {
+    type SystemData = (
+        ReadExpect<'a, String>,
+        ReadExpect<'a, Chunks>,
+        ReadExpect<'a, Players>,
+        WriteExpect<'a, InPortals>,
+        WriteExpect<'a, MessagesQueue>,
+        WriteStorage<'a, RigidBody>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (world_name, chunks, players, mut in_portals, mut messages, mut bodies) = data;
+
+        let dimension = chunks.config.dimension as f32;
+        let mut inside = HashSet::new();
+
+        for (id, player) in players.iter() {
+            let body = match bodies.get_mut(player.entity) {
+                Some(body) => body,
+                None => continue,
+            };
+
+            // either the feet or the head in a portal counts
+            let Vec3(px, py, pz) = body.get_position();
+            let portal = [py, py + dimension].iter().find_map(|&y| {
+                let id = chunks.get_voxel_by_world(px, y, pz);
+                chunks.registry.get_portal(id)
+            });
+
+            let portal = match portal {
+                Some(portal) => portal,
+                None => continue,
+            };
+
+            inside.insert(*id);
+
+            if in_portals.0.contains(id) {
+                continue;
+            }
+
+            let voxel = portal.position.map(|[vx, vy, vz]| Vec3(vx, vy, vz));
+
+            let message = if portal.world.is_empty() || portal.world == *world_name {
+                let voxel = match voxel {
+                    Some(voxel) => voxel,
+                    None => continue,
+                };
+
+                body.set_position(&voxel_to_standing(&voxel, dimension));
+                create_teleport_message(Some(&voxel), "")
+            } else {
+                // the session takes it from here, moving the player to the other world
+                create_teleport_message(voxel.as_ref(), &portal.world)
+            };
+
+            messages.push((message, Some(vec![*id]), None, None));
+        }
+
+        in_portals.0 = inside;
+    }
+}
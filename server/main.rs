@@ -5,7 +5,7 @@ use actix_cors::Cors;
 use actix_files as fs;
 use actix_web::{web, App, HttpServer};
 
-use server_core::network::{message, routes, server::WsServer};
+use server_core::network::{auth::Auth, message, routes, server::WsServer};
 
 fn setup_logger() -> Result<(), fern::InitError> {
     fern::Dispatch::new()
@@ -32,16 +32,36 @@ async fn main() -> std::io::Result<()> {
 
     let addr = "localhost:4000";
 
+    let auth = web::Data::new(
+        Auth::load("assets/metadata/auth.json").expect("Could not load authentication config."),
+    );
+
     let srv = HttpServer::new(move || {
         let cors = Cors::permissive();
 
         App::new()
             .wrap(cors)
+            .app_data(auth.clone())
             .route("/", web::get().to(routes::index))
             .route("/biomes", web::get().to(routes::index))
             .service(routes::worlds)
             .service(routes::world)
             .service(routes::time)
+            .service(routes::login)
+            .service(routes::admin_players)
+            .service(routes::admin_kick)
+            .service(routes::admin_ban)
+            .service(routes::admin_unban)
+            .service(routes::admin_broadcast)
+            .service(routes::admin_save)
+            .service(routes::admin_time)
+            .service(routes::admin_spawn)
+            .service(routes::admin_despawn)
+            .service(routes::admin_create_world)
+            .service(routes::admin_load_world)
+            .service(routes::admin_unload_world)
+            .service(routes::admin_delete_world)
+            .service(routes::admin_reload)
             .service(web::resource("/ws/").to(routes::ws_route))
             .service(fs::Files::new("/atlas/", "assets/textures/generated/").show_files_listing())
             .service(
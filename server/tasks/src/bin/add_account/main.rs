@@ -0,0 +1,27 @@
+use std::env;
+
+use server_core::network::auth::{AuthConfig, LocalAccounts};
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 3 {
+        println!("Usage: add_account <name> <password>");
+        return;
+    }
+
+    let config: AuthConfig = serde_json::from_reader(
+        std::fs::File::open("assets/metadata/auth.json").expect("auth.json not found."),
+    )
+    .expect("Invalid auth.json.");
+
+    let mut accounts = LocalAccounts::load(&config.accounts).unwrap_or_else(|e| panic!("{}", e));
+
+    match accounts.add(&args[1], &args[2]) {
+        Ok(identity) => {
+            accounts.save().unwrap_or_else(|e| panic!("{}", e));
+            println!("Created account \"{}\" ({}).", identity.name, identity.uuid);
+        }
+        Err(error) => println!("Could not create account: {}", error),
+    }
+}
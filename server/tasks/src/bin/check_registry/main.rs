@@ -0,0 +1,20 @@
+use std::process;
+
+use server_core::engine::{config::Configs, validation::validate_registry};
+
+fn main() {
+    let packs = Configs::load_packs();
+    let errors = validate_registry(&packs);
+
+    if errors.is_empty() {
+        println!("Registry is valid ({} texture packs).", packs.len());
+        return;
+    }
+
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+
+    eprintln!("Found {} problems in the registry.", errors.len());
+    process::exit(1);
+}
@@ -1,10 +1,15 @@
 use server_common::vec::Vec3;
+use server_core::engine::palette;
 use server_tasks::loop_through_chunks;
 
 fn main() {
-    println!("Cleaning ./data and removing non-existent blocks + corrupted chunk files...\n");
+    println!(
+        "Cleaning ./data and replacing non-existent blocks + removing corrupted chunk files...\n"
+    );
+
+    loop_through_chunks(&|chunk, registry, config| {
+        let placeholder = palette::placeholder(config, registry);
 
-    loop_through_chunks(&|chunk, registry| {
         let Vec3(start_x, start_y, start_z) = chunk.min;
         let Vec3(end_x, end_y, end_z) = chunk.max;
 
@@ -14,7 +19,7 @@ fn main() {
                     let id = chunk.get_voxel(vx, vy, vz);
 
                     if !registry.has_type(id) {
-                        chunk.set_voxel(vx, vy, vz, 0);
+                        chunk.set_voxel(vx, vy, vz, placeholder);
                     }
                 }
             }
@@ -4,13 +4,14 @@ use std::{
     str::FromStr,
 };
 
-use server_core::engine::{chunk::Chunk, config::Configs, registry::Registry};
+use server_core::constants::{CREATED_WORLDS_FILE, WORLDS_FILE};
+use server_core::engine::{chunk::Chunk, config::Configs, registry::Registry, world::WorldConfig};
 use server_utils::convert::parse_chunk_name;
 
 use indicatif::{ProgressBar, ProgressStyle};
 
-pub fn loop_through_chunks(func: &dyn Fn(&mut Chunk, &Registry)) {
-    let (configs, registry) = Configs::load_worlds("assets/metadata/worlds.json");
+pub fn loop_through_chunks(func: &dyn Fn(&mut Chunk, &Registry, &WorldConfig)) {
+    let (configs, registry) = Configs::load_worlds(WORLDS_FILE, CREATED_WORLDS_FILE);
 
     configs.into_iter().for_each(|(name, (_, config))| {
         if config.save {
@@ -47,7 +48,7 @@ pub fn loop_through_chunks(func: &dyn Fn(&mut Chunk, &Registry)) {
 
                 let mut chunk = Chunk::new(coords.to_owned(), &config, &path);
 
-                func(&mut chunk, &registry);
+                func(&mut chunk, &registry, &config);
 
                 pb.inc(1);
             }